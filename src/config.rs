@@ -15,6 +15,16 @@ pub struct Config {
     pub server_settings_file: String,
     pub player_count: usize,
     pub turn_limit: u32,
+    /// Damage dealt by the first draw from an empty deck.
+    pub fatigue_start: i32,
+    /// How much more damage each following empty draw deals.
+    pub fatigue_increment: i32,
+    /// Seed for the game's RNG. A random seed is used when not set.
+    pub game_seed: Option<u64>,
+    /// How many connections may watch a game once all players joined.
+    pub max_spectators: usize,
+    /// How long spectators wait before they see what happened, in milliseconds.
+    pub spectator_delay: u64,
 }
 impl Default for Config {
     fn default() -> Config {
@@ -25,6 +35,11 @@ impl Default for Config {
             server_settings_file: String::from("./server.config"),
             player_count: 2,
             turn_limit: 3,
+            fatigue_start: 1,
+            fatigue_increment: 1,
+            game_seed: None,
+            max_spectators: 8,
+            spectator_delay: 0,
         }
     }
 }
@@ -0,0 +1,477 @@
+//! Card effects written as text in the card pool, for example
+//! `"on play: deal 2 damage to target enemy minion and draw a card"`.
+//!
+//! Each line is parsed into an Ability: when it happens and the effects that happen.
+use crate::entity::{CardKey, TagKey};
+use crate::game::zones::ZoneName;
+use std::fmt;
+
+/// When an ability's effects happen.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum When {
+    /// The card was played from its owner's hand. Tokens and copies put into play do not count.
+    Play,
+    /// The card left the battlefield after it died.
+    Death,
+    /// The start of the owner's turn.
+    TurnStart,
+    /// The end of the owner's turn.
+    TurnEnd,
+    /// The card took damage.
+    Damaged,
+    /// Always, while the card is on the battlefield. Only buffs can be auras.
+    Aura,
+}
+
+/// How long a buff lasts.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum EffectDuration {
+    /// Until the buffed card leaves play.
+    Permanent,
+    /// Until the end of the turn it was given in.
+    EndOfTurn,
+    /// While the card the buff came from is on the battlefield.
+    WhileInPlay,
+}
+
+/// Whose cards or hero an effect targets, seen from the card's owner.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Side {
+    Friendly,
+    Enemy,
+    Any,
+}
+
+/// What an effect is done to.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Target {
+    /// The card the ability belongs to.
+    This,
+    Hero(Side),
+    /// One minion picked by the player.
+    ChosenMinion(Side),
+    RandomMinion(Side),
+    AllMinions(Side),
+    /// A card that was picked when the effect resolved.
+    Card(CardKey),
+}
+
+/// One thing an ability does. Nodes can be combined with All.
+#[derive(Clone, Debug, PartialEq)]
+pub enum EffectNode {
+    Damage(i32, Target),
+    Heal(i32, Target),
+    /// The owner draws this many cards.
+    Draw(usize),
+    /// The owner summons a card by name.
+    Summon(String),
+    /// A token by name is put into one of the owner's zones.
+    Create(String, ZoneName),
+    /// Token copies of the targeted cards are put into one of the owner's zones.
+    Copy(Target, ZoneName),
+    /// The owner's hero gains this much armor.
+    GainArmor(i32),
+    /// Gives the targeted cards a keyword such as Taunt.
+    Keyword(TagKey, Target),
+    /// The owner takes control of the targeted cards.
+    TakeControl(Target),
+    /// Adds to a tag of the targeted cards for as long as the duration.
+    Buff(TagKey, i32, Target, EffectDuration),
+    /// Every effect in order.
+    All(Vec<EffectNode>),
+}
+
+/// A parsed line of card effect text.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Ability {
+    pub when: When,
+    pub effect: EffectNode,
+}
+
+/// Why a line of card effect text could not be parsed.
+#[derive(Clone, Debug, PartialEq)]
+pub enum AbilityError {
+    /// The line has no "when: effects" form.
+    MissingColon(String),
+    UnknownTrigger(String),
+    /// No effect has this name.
+    UnknownEffect(String),
+    /// The effect is known but its amount, target or card could not be read.
+    InvalidEffect(String),
+}
+impl fmt::Display for AbilityError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            AbilityError::MissingColon(s) => write!(f, "expected 'when: effect' in '{}'", s),
+            AbilityError::UnknownTrigger(s) => write!(f, "unknown trigger '{}'", s),
+            AbilityError::UnknownEffect(s) => write!(f, "unknown effect '{}'", s),
+            AbilityError::InvalidEffect(s) => write!(f, "invalid effect '{}'", s),
+        }
+    }
+}
+
+impl EffectNode {
+    /// The side of the first target the player picks in this effect, if any.
+    pub fn chosen_side(&self) -> Option<Side> {
+        match self {
+            EffectNode::Damage(_, Target::ChosenMinion(side))
+            | EffectNode::Heal(_, Target::ChosenMinion(side))
+            | EffectNode::Keyword(_, Target::ChosenMinion(side))
+            | EffectNode::Copy(Target::ChosenMinion(side), _)
+            | EffectNode::TakeControl(Target::ChosenMinion(side))
+            | EffectNode::Buff(_, _, Target::ChosenMinion(side), _) => Some(*side),
+            EffectNode::All(effects) => effects.iter().find_map(EffectNode::chosen_side),
+            _ => None,
+        }
+    }
+}
+
+impl Ability {
+    /// Parses one line of card effect text.
+    pub fn parse(text: &str) -> Result<Ability, AbilityError> {
+        let text = text.trim();
+        let colon = text
+            .find(':')
+            .ok_or_else(|| AbilityError::MissingColon(String::from(text)))?;
+        let (when, effects) = (text[..colon].trim(), text[colon + 1..].trim());
+        let when = parse_when(&when.to_lowercase())?;
+        let mut nodes = Vec::new();
+        for effect in split_clauses(effects) {
+            let node = match (when, parse_effect(effect.trim())?) {
+                (When::Aura, EffectNode::Buff(tag, amount, target, _)) => {
+                    EffectNode::Buff(tag, amount, target, EffectDuration::WhileInPlay)
+                }
+                (When::Aura, _) => return Err(AbilityError::InvalidEffect(String::from(effect))),
+                (_, node) => node,
+            };
+            nodes.push(node);
+        }
+        let effect = if nodes.len() == 1 {
+            nodes.remove(0)
+        } else {
+            EffectNode::All(nodes)
+        };
+        Ok(Ability { when, effect })
+    }
+}
+
+/// Splits effect text at each " and " that starts a new effect.
+/// An "and" inside a card name, as in "add Salt and Pepper to your hand", does not split.
+fn split_clauses(text: &str) -> Vec<&str> {
+    let mut clauses = Vec::new();
+    let mut start = 0;
+    for (at, _) in text.match_indices(" and ") {
+        let next = text[at + 5..].split_whitespace().next().unwrap_or("");
+        if KNOWN_EFFECTS.contains(&next.to_lowercase().as_str()) {
+            clauses.push(&text[start..at]);
+            start = at + 5;
+        }
+    }
+    clauses.push(&text[start..]);
+    clauses
+}
+
+fn parse_when(text: &str) -> Result<When, AbilityError> {
+    match text {
+        "on play" | "battlecry" => Ok(When::Play),
+        "on death" | "deathrattle" => Ok(When::Death),
+        "at the start of your turn" | "on turn start" => Ok(When::TurnStart),
+        "at the end of your turn" | "on turn end" => Ok(When::TurnEnd),
+        "when damaged" | "on damaged" => Ok(When::Damaged),
+        "aura" | "while in play" => Ok(When::Aura),
+        _ => Err(AbilityError::UnknownTrigger(String::from(text))),
+    }
+}
+
+fn parse_effect(text: &str) -> Result<EffectNode, AbilityError> {
+    let invalid = || AbilityError::InvalidEffect(String::from(text));
+    let lower = text.to_lowercase();
+    let words: Vec<&str> = lower.split_whitespace().collect();
+    match words.as_slice() {
+        ["deal", amount, "damage", "to", target @ ..] => Ok(EffectNode::Damage(
+            parse_amount(amount).ok_or_else(invalid)?,
+            parse_target(target).ok_or_else(invalid)?,
+        )),
+        ["restore", amount, "health", "to", target @ ..] => Ok(EffectNode::Heal(
+            parse_amount(amount).ok_or_else(invalid)?,
+            parse_target(target).ok_or_else(invalid)?,
+        )),
+        ["draw", amount, "card"] | ["draw", amount, "cards"] => {
+            let amount = parse_amount(amount).ok_or_else(invalid)?;
+            Ok(EffectNode::Draw(amount.max(0) as usize))
+        }
+        ["gain", amount, "armor"] => Ok(EffectNode::GainArmor(
+            parse_amount(amount).ok_or_else(invalid)?,
+        )),
+        ["take", "control", "of", target @ ..] => Ok(EffectNode::TakeControl(
+            parse_target(target).ok_or_else(invalid)?,
+        )),
+        ["summon", "a", "copy", "of", target @ ..] => Ok(EffectNode::Copy(
+            parse_target(target).ok_or_else(invalid)?,
+            ZoneName::Battlefield,
+        )),
+        ["add", "a", "copy", "of", target @ .., "to", "your", "hand"] => Ok(EffectNode::Copy(
+            parse_target(target).ok_or_else(invalid)?,
+            ZoneName::Hand,
+        )),
+        ["add", _, .., "to", "your", "hand"] => {
+            let name = card_name(text, 3).ok_or_else(invalid)?;
+            Ok(EffectNode::Create(name, ZoneName::Hand))
+        }
+        ["shuffle", _, .., "into", "your", "deck"] => {
+            let name = card_name(text, 3).ok_or_else(invalid)?;
+            Ok(EffectNode::Create(name, ZoneName::Deck))
+        }
+        ["summon", name @ ..] => {
+            if name.is_empty() {
+                return Err(invalid());
+            }
+            let name = card_name(text, 0).ok_or_else(invalid)?;
+            Ok(EffectNode::Summon(name))
+        }
+        ["give", rest @ ..] if rest.len() >= 3 => {
+            let (rest, duration) = match rest {
+                [rest @ .., "until", "end", "of", "turn"] => (rest, EffectDuration::EndOfTurn),
+                _ => (rest, EffectDuration::Permanent),
+            };
+            if let Some((tag, target)) = parse_keyword(rest) {
+                // Keywords are tags on the card and can not wear off.
+                if duration != EffectDuration::Permanent {
+                    return Err(invalid());
+                }
+                let target = parse_target(target).ok_or_else(invalid)?;
+                return Ok(EffectNode::Keyword(tag, target));
+            }
+            if rest.len() < 3 {
+                return Err(invalid());
+            }
+            let (target, buff) = rest.split_at(rest.len() - 2);
+            let target = parse_target(target).ok_or_else(invalid)?;
+            let amount = buff[0]
+                .strip_prefix('+')
+                .and_then(|n| n.parse().ok())
+                .ok_or_else(invalid)?;
+            match buff[1] {
+                "attack" => Ok(EffectNode::Buff(TagKey::Attack, amount, target, duration)),
+                "health" => Ok(EffectNode::Buff(TagKey::Health, amount, target, duration)),
+                _ => Err(invalid()),
+            }
+        }
+        [name, ..] if KNOWN_EFFECTS.contains(name) => Err(invalid()),
+        [name, ..] => Err(AbilityError::UnknownEffect(String::from(*name))),
+        [] => Err(invalid()),
+    }
+}
+
+/// The first word of every effect that can be parsed.
+const KNOWN_EFFECTS: &[&str] = &[
+    "deal", "restore", "draw", "gain", "summon", "give", "add", "shuffle", "take",
+];
+
+/// Reads the card name after the first word, and "a" or "an", of an effect.
+/// Leaves off the last words. Card names keep the case they were written with.
+fn card_name(text: &str, last_words: usize) -> Option<String> {
+    let words: Vec<&str> = text.split_whitespace().collect();
+    let start = match words.get(1).map(|w| w.to_lowercase()) {
+        Some(ref w) if w == "a" || w == "an" => 2,
+        _ => 1,
+    };
+    let end = words.len().checked_sub(last_words)?;
+    if start >= end {
+        return None;
+    }
+    Some(words[start..end].join(" "))
+}
+
+/// Reads a keyword at the end of the words. Returns it and the words before it.
+fn parse_keyword<'a, 'b>(words: &'a [&'b str]) -> Option<(TagKey, &'a [&'b str])> {
+    match words {
+        [rest @ .., "divine", "shield"] => Some((TagKey::DivineShield, rest)),
+        [rest @ .., last] => {
+            let tag = match *last {
+                "taunt" => TagKey::Taunt,
+                "charge" => TagKey::Charge,
+                "stealth" => TagKey::Stealth,
+                "lifesteal" => TagKey::Lifesteal,
+                "poisonous" => TagKey::Poisonous,
+                "windfury" => TagKey::Windfury,
+                _ => return None,
+            };
+            Some((tag, rest))
+        }
+        [] => None,
+    }
+}
+
+/// Reads a number or "a"/"an" as one.
+fn parse_amount(word: &str) -> Option<i32> {
+    match word {
+        "a" | "an" => Some(1),
+        _ => word.parse().ok(),
+    }
+}
+
+fn parse_target(words: &[&str]) -> Option<Target> {
+    let words = match words {
+        ["a", rest @ ..] | ["an", rest @ ..] | ["the", rest @ ..] => rest,
+        _ => words,
+    };
+    match words {
+        ["itself"] | ["this", "minion"] | ["self"] => Some(Target::This),
+        ["your", "hero"] => Some(Target::Hero(Side::Friendly)),
+        ["enemy", "hero"] => Some(Target::Hero(Side::Enemy)),
+        ["all", "heroes"] => Some(Target::Hero(Side::Any)),
+        ["target", rest @ ..] => match parse_side(rest) {
+            (side, ["minion"]) => Some(Target::ChosenMinion(side)),
+            _ => None,
+        },
+        ["random", rest @ ..] => match parse_side(rest) {
+            (side, ["minion"]) => Some(Target::RandomMinion(side)),
+            _ => None,
+        },
+        ["all", rest @ ..] => match parse_side(rest) {
+            (side, ["minions"]) => Some(Target::AllMinions(side)),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+/// Reads an optional "enemy" or "friendly" and returns the words after it.
+fn parse_side<'a, 'b>(words: &'a [&'b str]) -> (Side, &'a [&'b str]) {
+    match words {
+        ["enemy", rest @ ..] => (Side::Enemy, rest),
+        ["friendly", rest @ ..] | ["your", rest @ ..] => (Side::Friendly, rest),
+        _ => (Side::Any, words),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn effect(text: &str) -> EffectNode {
+        Ability::parse(text).unwrap().effect
+    }
+
+    #[test]
+    fn parses_damage() {
+        assert_eq!(
+            effect("on play: deal 2 damage to target enemy minion"),
+            EffectNode::Damage(2, Target::ChosenMinion(Side::Enemy))
+        );
+        assert_eq!(
+            effect("battlecry: deal a damage to all heroes"),
+            EffectNode::Damage(1, Target::Hero(Side::Any))
+        );
+    }
+
+    #[test]
+    fn parses_buff_until_end_of_turn() {
+        assert_eq!(
+            effect("on play: give target friendly minion +2 attack until end of turn"),
+            EffectNode::Buff(
+                TagKey::Attack,
+                2,
+                Target::ChosenMinion(Side::Friendly),
+                EffectDuration::EndOfTurn
+            )
+        );
+        assert_eq!(
+            effect("on play: give all minions +1 health"),
+            EffectNode::Buff(
+                TagKey::Health,
+                1,
+                Target::AllMinions(Side::Any),
+                EffectDuration::Permanent
+            )
+        );
+    }
+
+    #[test]
+    fn aura_buffs_last_while_in_play() {
+        assert_eq!(
+            effect("aura: give all friendly minions +1 attack"),
+            EffectNode::Buff(
+                TagKey::Attack,
+                1,
+                Target::AllMinions(Side::Friendly),
+                EffectDuration::WhileInPlay
+            )
+        );
+        assert!(Ability::parse("aura: draw a card").is_err());
+    }
+
+    #[test]
+    fn parses_copy_to_hand() {
+        assert_eq!(
+            effect("on death: add a copy of target enemy minion to your hand"),
+            EffectNode::Copy(Target::ChosenMinion(Side::Enemy), ZoneName::Hand)
+        );
+        assert_eq!(
+            effect("on play: add a Fire Imp to your hand"),
+            EffectNode::Create(String::from("Fire Imp"), ZoneName::Hand)
+        );
+    }
+
+    #[test]
+    fn parses_keywords() {
+        assert_eq!(
+            effect("on play: give itself divine shield"),
+            EffectNode::Keyword(TagKey::DivineShield, Target::This)
+        );
+        assert_eq!(
+            effect("on play: give a random friendly minion taunt"),
+            EffectNode::Keyword(TagKey::Taunt, Target::RandomMinion(Side::Friendly))
+        );
+    }
+
+    #[test]
+    fn keywords_can_not_last_until_end_of_turn() {
+        assert_eq!(
+            Ability::parse("on play: give target minion charge until end of turn"),
+            Err(AbilityError::InvalidEffect(String::from(
+                "give target minion charge until end of turn"
+            )))
+        );
+    }
+
+    #[test]
+    fn card_names_can_contain_and() {
+        assert_eq!(
+            effect("on play: add Salt and Pepper to your hand and draw a card"),
+            EffectNode::All(vec![
+                EffectNode::Create(String::from("Salt and Pepper"), ZoneName::Hand),
+                EffectNode::Draw(1),
+            ])
+        );
+    }
+
+    #[test]
+    fn parses_several_effects() {
+        assert_eq!(
+            effect("on play: draw 2 cards and gain 3 armor"),
+            EffectNode::All(vec![EffectNode::Draw(2), EffectNode::GainArmor(3)])
+        );
+    }
+
+    #[test]
+    fn reports_errors() {
+        assert_eq!(
+            Ability::parse("deal 2 damage to enemy hero"),
+            Err(AbilityError::MissingColon(String::from("deal 2 damage to enemy hero")))
+        );
+        assert_eq!(
+            Ability::parse("sometimes: draw a card"),
+            Err(AbilityError::UnknownTrigger(String::from("sometimes")))
+        );
+        assert_eq!(
+            Ability::parse("on play: dance"),
+            Err(AbilityError::UnknownEffect(String::from("dance")))
+        );
+        assert_eq!(
+            Ability::parse("on play: deal many damage to enemy hero"),
+            Err(AbilityError::InvalidEffect(String::from("deal many damage to enemy hero")))
+        );
+    }
+}
@@ -1,7 +1,7 @@
 use crate::entity::cardpool::{CardPool,PooledCardData};
 use crate::entity::{Dispatch, TagKey, TagVal, Trigger};
 use crate::game::player::Player;
-use crate::game::script::{Script, ScriptManager};
+use crate::game::script::{AbilityScript, Script, ScriptManager};
 use crate::utils::vecmap::IndexKey;
 use std::collections::HashMap;
 use std::fmt;
@@ -13,7 +13,10 @@ pub struct Card {
     //key: CardKey,
     name: String,
     text: String,
+    /// Base values. Changed only by damage, healing and set_tag.
     tags: HashMap<TagKey, TagVal>,
+    /// Added to the base values by persistent effects. Recomputed whenever the board changes.
+    modifiers: HashMap<TagKey, i32>,
     script: Script,
 }
 
@@ -47,7 +50,12 @@ impl Card {
             name: (String::from(data.name())),
             text: (String::from(data.text())),
             tags: (data.clone_tags()),
-            script: ScriptManager::get(data.script()),
+            modifiers: HashMap::new(),
+            script: if data.abilities().is_empty() {
+                ScriptManager::get(data.script())
+            } else {
+                Box::new(AbilityScript::new(data.abilities()))
+            },
         }
     }
     pub fn new(key: CardKey, name: &str) -> Card {
@@ -58,11 +66,28 @@ impl Card {
                 name: (String::from("Unknown Card")),
                 text: (String::from("")),
                 tags: HashMap::new(),
+                modifiers: HashMap::new(),
                 script: ScriptManager::get("none"),
             }
         }
     }
 
+    /// A new card with the same name, script and base tags as this one.
+    pub fn copy(&self) -> Card {
+        let mut tags = self.tags.clone();
+        // The copy is a new card, so what happened to this one this turn does not carry over.
+        for key in &[TagKey::Damage, TagKey::Exhausted, TagKey::Attacks, TagKey::Destroyed] {
+            tags.remove(key);
+        }
+        Card {
+            name: self.name.clone(),
+            text: self.text.clone(),
+            tags,
+            modifiers: HashMap::new(),
+            script: self.script.box_clone(),
+        }
+    }
+
     #[inline]
     pub fn name(&self) -> &str {
         &self.name
@@ -72,18 +97,40 @@ impl Card {
         &self.text
     }
     #[inline]
+    pub fn script(&self) -> &Script {
+        &self.script
+    }
+    #[inline]
     pub fn tags_len(&self) -> usize {
         self.tags.len()
     }
 
+    /// The computed value of a tag: its base value with every modifier added.
     pub fn get_tag(&self, key: &TagKey) -> TagVal {
+        let base = self.get_base_tag(key);
+        match self.modifiers.get(key) {
+            Some(amount) => TagVal::from(base.as_i32() + amount),
+            None => base,
+        }
+    }
+    /// The value of a tag without any modifiers.
+    pub fn get_base_tag(&self, key: &TagKey) -> TagVal {
         *self.tags.get(key).unwrap_or(&TagVal::None)
     }
+    /// Sets the base value of a tag and returns the old base value.
     pub fn set_tag(&mut self, key: TagKey, val: TagVal) -> TagVal {
         self.tags
             .insert(key, val)
             .unwrap_or(TagVal::None)
     }
+    /// Adds to the computed value of a tag.
+    pub fn add_modifier(&mut self, key: TagKey, amount: i32) {
+        *self.modifiers.entry(key).or_insert(0) += amount;
+    }
+    /// Removes all modifiers so they can be recomputed.
+    pub fn clear_modifiers(&mut self) {
+        self.modifiers.clear();
+    }
 
     pub fn base_attack(&self) -> i32 {
         self.get_tag(&TagKey::BaseAttack).as_i32()
@@ -95,14 +142,77 @@ impl Card {
     pub fn base_health(&self) -> i32 {
         self.get_tag(&TagKey::BaseHealth).as_i32()
     }
+    /// Health with every modifier added and the damage taken removed.
     pub fn current_health(&self) -> i32 {
-        self.get_tag(&TagKey::Health).as_i32()
+        self.max_health() - self.missing_health()
     }
     pub fn max_health(&self) -> i32 {
-        self.get_tag(&TagKey::MaxHealth).as_i32()
+        self.get_tag(&TagKey::Health).as_i32()
     }
     pub fn missing_health(&self) -> i32 {
-        self.max_health() - self.current_health()
+        self.get_base_tag(&TagKey::Damage).as_i32()
+    }
+    /// Lowers the damage taken so a card left at 0 health by a lost buff stays at 1.
+    pub fn keep_alive(&mut self) {
+        let health = self.current_health();
+        if health <= 0 {
+            let damage = self.missing_health() + health - 1;
+            self.set_tag(TagKey::Damage, TagVal::from(damage.max(0)));
+        }
+    }
+    /// True when the card has no health left and should be destroyed.
+    pub fn is_dead(&self) -> bool {
+        self.current_health() <= 0 || self.has(TagKey::Destroyed)
+    }
+    /// True if a tag such as a keyword is set on the card.
+    pub fn has(&self, key: TagKey) -> bool {
+        self.get_tag(&key).as_bool()
+    }
+    /// True if the card is on the battlefield long enough and has attacks left this turn.
+    pub fn can_attack(&self) -> bool {
+        let max_attacks = if self.has(TagKey::Windfury) { 2 } else { 1 };
+        self.current_attack() > 0
+            && (!self.has(TagKey::Exhausted) || self.has(TagKey::Charge))
+            && self.get_tag(&TagKey::Attacks).as_i32() < max_attacks
+    }
+    /// Counts an attack. Attacking reveals a card with Stealth.
+    pub fn use_attack(&mut self) {
+        let attacks = self.get_base_tag(&TagKey::Attacks).as_i32() + 1;
+        self.set_tag(TagKey::Attacks, TagVal::from(attacks));
+        self.set_tag(TagKey::Stealth, TagVal::from(false));
+    }
+    /// Called when the card enters the battlefield.
+    pub fn enter_play(&mut self) {
+        self.set_tag(TagKey::Exhausted, TagVal::from(true));
+        self.set_tag(TagKey::Attacks, TagVal::from(0));
+    }
+    /// Called at the start of the owner's turn for each card on their battlefield.
+    pub fn start_turn(&mut self) {
+        self.set_tag(TagKey::Exhausted, TagVal::from(false));
+        self.set_tag(TagKey::Attacks, TagVal::from(0));
+    }
+    /// Adds amount to the Damage tag, which lowers current health.
+    /// Divine Shield prevents all of it once.
+    /// Returns the damage that was dealt.
+    pub fn take_damage(&mut self, amount: i32) -> i32 {
+        if amount <= 0 {
+            return 0;
+        }
+        if self.has(TagKey::DivineShield) {
+            self.set_tag(TagKey::DivineShield, TagVal::from(false));
+            return 0;
+        }
+        let damage = self.missing_health() + amount;
+        self.set_tag(TagKey::Damage, TagVal::from(damage));
+        amount
+    }
+    /// Restores up to amount of missing health by lowering the Damage tag.
+    /// Returns the health that was restored.
+    pub fn heal(&mut self, amount: i32) -> i32 {
+        let healed = amount.min(self.missing_health()).max(0);
+        let damage = self.missing_health() - healed;
+        self.set_tag(TagKey::Damage, TagVal::from(damage));
+        healed
     }
 
     pub fn base_cost(&self) -> i32 {
@@ -112,11 +222,16 @@ impl Card {
         self.get_tag(&TagKey::Cost).as_i32()
     }
 
-    pub fn on_card_drawn(&mut self, player: &mut Player) {
-        info!("on_card_drawn!");
-        Dispatch::broadcast(Trigger::OnCardDrawn(player, self))
+    pub fn on_card_drawn(&mut self, dispatch: &mut Dispatch, player: &mut Player) {
+        dispatch.broadcast(Trigger::OnCardDrawn(player, self));
+    }
+    pub fn after_card_drawn(&mut self, dispatch: &mut Dispatch, _player: &mut Player) {
+        dispatch.broadcast(Trigger::AfterCardDrawn(self));
+    }
+    pub fn on_card_death(&mut self, dispatch: &mut Dispatch, player: &mut Player) {
+        dispatch.broadcast(Trigger::OnCardDeath(player, self));
     }
-    pub fn after_card_drawn(&mut self, _player: &mut Player) {
-        Dispatch::broadcast(Trigger::AfterCardDrawn(self))
+    pub fn after_card_death(&mut self, dispatch: &mut Dispatch, _player: &mut Player) {
+        dispatch.broadcast(Trigger::AfterCardDeath(self));
     }
 }
@@ -1,4 +1,4 @@
-use crate::entity::{TagKey, TagVal};
+use crate::entity::{Ability, TagKey, TagVal};
 use serde_json;
 use std::collections::HashMap;
 use std::fs::{self, File};
@@ -12,6 +12,13 @@ pub struct PooledCardData {
     text: String,
     script: String,
     tags: HashMap<TagKey, TagVal>,
+    /// Effect text such as "on play: deal 2 damage to target enemy minion".
+    /// Used instead of script when there is any.
+    #[serde(default)]
+    effects: Vec<String>,
+    /// The effects parsed when the pool is loaded.
+    #[serde(skip)]
+    abilities: Vec<Ability>,
 }
 impl PooledCardData {
     pub fn new(name: &str) -> PooledCardData {
@@ -20,6 +27,8 @@ impl PooledCardData {
             text: String::new(),
             script: String::from("none"),
             tags: HashMap::with_capacity(8),
+            effects: Vec::new(),
+            abilities: Vec::new(),
         }
     }
 
@@ -41,6 +50,27 @@ impl PooledCardData {
     pub fn script(&self) -> &str {
         &self.script
     }
+    #[inline]
+    pub fn abilities(&self) -> &[Ability] {
+        &self.abilities
+    }
+
+    /// Parses the effect text into abilities.
+    /// Effects that can not be parsed are skipped and reported.
+    fn parse_effects(&mut self) -> usize {
+        let mut errors = 0;
+        self.abilities.clear();
+        for text in self.effects.iter() {
+            match Ability::parse(text) {
+                Ok(ability) => self.abilities.push(ability),
+                Err(e) => {
+                    warn!("Card '{}' has an effect that was skipped: {}", self.name, e);
+                    errors += 1;
+                }
+            }
+        }
+        errors
+    }
 }
 
 lazy_static! {
@@ -56,7 +86,11 @@ impl CardPool {
     }
     pub fn from_disk() -> io::Result<CardPool> {
         let file = File::open("./output/cards_out.json")?;
-        let by_name: HashMap<String, PooledCardData> = serde_json::from_reader(file)?;
+        let mut by_name: HashMap<String, PooledCardData> = serde_json::from_reader(file)?;
+        let errors: usize = by_name.values_mut().map(PooledCardData::parse_effects).sum();
+        if errors > 0 {
+            warn!("{} card effects could not be parsed.", errors);
+        }
         Ok(CardPool {
             by_name,
         })
@@ -78,3 +112,20 @@ impl CardPool {
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_effects_skips_and_counts_errors() {
+        let mut card = PooledCardData::new("Test");
+        card.effects = vec![
+            String::from("on play: draw a card"),
+            String::from("on play: dance"),
+            String::from("no colon here"),
+        ];
+        assert_eq!(card.parse_effects(), 2);
+        assert_eq!(card.abilities().len(), 1);
+    }
+}
@@ -1,19 +1,66 @@
+use crate::entity::ability::EffectDuration;
+use crate::entity::{CardKey, EffectNode};
+use crate::game::PlayerId;
+
 // An Effect represents any card text, passive or active effect that changes any part of the game
+#[derive(Clone, Debug)]
 pub struct Effect {
     name: String,
     active: bool,
     persistant: bool,
+    /// The card the effect came from.
+    source: CardKey,
+    /// The player who controls the effect.
+    owner: PlayerId,
+    node: EffectNode,
 }
 
 impl Effect {
-    pub fn new(name: &str, persistant: bool) -> Effect {
+    pub fn new(name: &str, source: CardKey, owner: PlayerId, node: EffectNode) -> Effect {
         Effect {
             name: String::from(name),
             active: true,
-            persistant,
+            persistant: false,
+            source,
+            owner,
+            node,
         }
     }
 
+    /// A buff that stays on the board instead of resolving once.
+    pub fn persistent(name: &str, source: CardKey, owner: PlayerId, node: EffectNode) -> Effect {
+        Effect {
+            persistant: true,
+            ..Effect::new(name, source, owner, node)
+        }
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+    pub fn source(&self) -> CardKey {
+        self.source
+    }
+    pub fn owner(&self) -> PlayerId {
+        self.owner
+    }
+    /// Hands the effect to another player, for example when its source changes controller.
+    pub fn set_owner(&mut self, owner: PlayerId) {
+        self.owner = owner
+    }
+    pub fn node(&self) -> &EffectNode {
+        &self.node
+    }
+    pub fn is_persistant(&self) -> bool {
+        self.persistant
+    }
+    /// How long a persistent buff lasts. None for effects that resolve once.
+    pub fn duration(&self) -> Option<EffectDuration> {
+        match self.node {
+            EffectNode::Buff(_, _, _, duration) if self.persistant => Some(duration),
+            _ => None,
+        }
+    }
     pub fn is_active(&self) -> bool {
         self.active
     }
@@ -1,6 +1,159 @@
 use crate::entity::HeroicWeapon;
+
+/// The starting health for all heroes.
+pub const DEFAULT_HERO_HEALTH: i32 = 30;
+
+/// The class a hero belongs to. Picks the hero's power.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, Serialize, Deserialize)]
+pub enum HeroClass {
+    #[default]
+    Neutral,
+    Warrior,
+    Rogue,
+    Hunter,
+    Priest,
+}
+
+impl HeroClass {
+    pub fn hero_power(self) -> HeroPower {
+        match self {
+            HeroClass::Neutral => HeroPower::None,
+            HeroClass::Warrior => HeroPower::ArmorUp,
+            HeroClass::Rogue => HeroPower::DaggerMastery,
+            HeroClass::Hunter => HeroPower::SteadyShot,
+            HeroClass::Priest => HeroPower::LesserHeal,
+        }
+    }
+}
+
+/// A hero's once per turn ability.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum HeroPower {
+    /// This hero has no power.
+    None,
+    /// Gain 2 armor.
+    ArmorUp,
+    /// Equip a 1/2 dagger.
+    DaggerMastery,
+    /// Deal 2 damage to each enemy hero.
+    SteadyShot,
+    /// Restore 2 health to your hero.
+    LesserHeal,
+}
+
+impl HeroPower {
+    pub fn cost(self) -> i32 {
+        match self {
+            HeroPower::None => 0,
+            _ => 2,
+        }
+    }
+}
+
+/// A hero represents the player on the board and holds their life total.
+#[derive(Clone)]
 pub struct Hero {
-    hero_class: u64,
-    weapon:  Option<HeroicWeapon>,
+    hero_class: HeroClass,
+    weapon: Option<HeroicWeapon>,
+    health: i32,
+    max_health: i32,
+    armor: i32,
+    hero_power_used: bool,
+    attacked: bool,
+}
+
+impl Hero {
+    pub fn new(hero_class: HeroClass) -> Hero {
+        Hero {
+            hero_class,
+            weapon: None,
+            health: DEFAULT_HERO_HEALTH,
+            max_health: DEFAULT_HERO_HEALTH,
+            armor: 0,
+            hero_power_used: false,
+            attacked: false,
+        }
+    }
+
+    pub fn hero_class(&self) -> HeroClass {
+        self.hero_class
+    }
+    pub fn hero_power(&self) -> HeroPower {
+        self.hero_class.hero_power()
+    }
+    pub fn health(&self) -> i32 {
+        self.health
+    }
+    pub fn max_health(&self) -> i32 {
+        self.max_health
+    }
+    pub fn armor(&self) -> i32 {
+        self.armor
+    }
+    /// True when the hero has no health left and their player has lost.
+    pub fn is_dead(&self) -> bool {
+        self.health <= 0
+    }
+
+    /// Called at the start of the owner's turn.
+    pub fn start_turn(&mut self) {
+        self.hero_power_used = false;
+        self.attacked = false;
+    }
+
+    /// Lowers armor then health by amount.
+    /// Returns the damage taken off health, which leaves out what armor absorbed.
+    pub fn take_damage(&mut self, amount: i32) -> i32 {
+        if amount <= 0 {
+            return 0;
+        }
+        let absorbed = Ord::min(self.armor, amount);
+        self.armor -= absorbed;
+        self.health -= amount - absorbed;
+        amount - absorbed
+    }
+    /// Raises health by amount up to max health. Returns the amount healed.
+    pub fn heal(&mut self, amount: i32) -> i32 {
+        let healed = Ord::max(Ord::min(amount, self.max_health - self.health), 0);
+        self.health += healed;
+        healed
+    }
+    pub fn gain_armor(&mut self, amount: i32) {
+        self.armor += Ord::max(amount, 0);
+    }
+
+    pub fn can_use_hero_power(&self) -> bool {
+        !self.hero_power_used && self.hero_power() != HeroPower::None
+    }
+    pub fn set_hero_power_used(&mut self) {
+        self.hero_power_used = true;
+    }
 
-}
\ No newline at end of file
+    pub fn weapon(&self) -> Option<&HeroicWeapon> {
+        self.weapon.as_ref()
+    }
+    /// Equips the weapon and returns the old weapon if one was equipped.
+    pub fn equip(&mut self, weapon: HeroicWeapon) -> Option<HeroicWeapon> {
+        self.weapon.replace(weapon)
+    }
+    /// The damage this hero deals when attacking.
+    pub fn attack(&self) -> i32 {
+        self.weapon.as_ref().map_or(0, HeroicWeapon::attack)
+    }
+    pub fn can_attack(&self) -> bool {
+        !self.attacked && self.attack() > 0
+    }
+    /// Marks the hero as having attacked and uses the weapon's durability.
+    /// Returns the damage the attack deals.
+    pub fn use_attack(&mut self) -> i32 {
+        let attack = self.attack();
+        self.attacked = true;
+        if let Some(weapon) = self.weapon.as_mut() {
+            if weapon.lose_durability() {
+                info!("{} broke.", weapon.name());
+                self.weapon = None;
+            }
+        }
+        attack
+    }
+}
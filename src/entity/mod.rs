@@ -5,11 +5,13 @@ pub mod cardpool;
 pub mod tags;
 pub mod trigger;
 pub mod effect;
+pub mod ability;
 
-pub use self::trigger::{Dispatch,Trigger};
+pub use self::trigger::{Dispatch, Trigger, TriggerKind};
 pub use self::tags::{TagKey,TagVal};
 pub use self::card::{Card,CardKey};
 pub use self::cardpool::CardPool;
-pub use self::hero::Hero;
+pub use self::hero::{Hero, HeroClass, HeroPower};
 pub use self::weapon::HeroicWeapon;
-pub use self::effect::Effect;
\ No newline at end of file
+pub use self::effect::Effect;
+pub use self::ability::{Ability, EffectNode};
\ No newline at end of file
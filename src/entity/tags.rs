@@ -11,6 +11,36 @@ pub enum TagKey {
     BaseHealth,
     MaxHealth,
     Damage,
+    Durability,
+    /// The card can be played in response to effects on the stack.
+    Instant,
+
+    // Keywords
+    /// Enemies must attack this card before other cards or the hero.
+    Taunt,
+    /// Can attack the turn it is played.
+    Charge,
+    /// Prevents the next damage the card would take.
+    DivineShield,
+    /// Can not be attacked or targeted by enemies until it attacks.
+    Stealth,
+    /// Damage dealt by this card heals its owner's hero.
+    Lifesteal,
+    /// Destroys any card damaged by this card.
+    Poisonous,
+    /// Can attack twice each turn.
+    Windfury,
+
+    // Set by the game
+    /// Entered the battlefield this turn and can not attack yet.
+    Exhausted,
+    /// The number of times the card attacked this turn.
+    Attacks,
+    /// Destroyed no matter how much health it has left.
+    Destroyed,
+    /// Created during the game instead of coming from a deck.
+    /// Removed from the game when it leaves the battlefield.
+    Token,
 }
 
 #[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
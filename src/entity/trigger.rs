@@ -1,35 +1,113 @@
-use crate::game::game_state::Game;
-use crate::entity::Card;
-use crate::game::Player;
+use crate::entity::{Card, CardKey};
+use crate::game::stage::GameResults;
+use crate::game::zones::ZoneName;
+use crate::game::{Player, PlayerId};
+use std::cmp::Reverse;
 use std::collections::HashMap;
-use crate::game::script::GameScript;
+use crate::game::script::{GameHandle, ScriptCommand};
 use std::fmt;
 
 pub enum Trigger<'a> {
     OnCardDrawn(&'a mut Player, &'a mut Card),
     AfterCardDrawn(&'a mut Card),
     OnCardDrawFail(&'a mut Player),
+    /// A card was drawn with a full hand and put in the graveyard.
+    OnCardBurned(&'a mut Player, CardKey),
     OnPlayCard(&'a mut Player, &'a mut Card, bool),
-    OnTurnStart(),
-    OnTurnEnd(),
+    /// A card was played from the player's hand and is on the battlefield.
+    /// Not sent for tokens and copies that are put there by effects.
+    OnCardPlayed(CardKey, PlayerId),
+    OnCardDeath(&'a mut Player, &'a mut Card),
+    AfterCardDeath(&'a mut Card),
+    /// A card was put into one of the player's zones.
+    OnCardEnterZone(CardKey, PlayerId, ZoneName),
+    /// A card was taken out of one of the player's zones.
+    OnCardLeaveZone(CardKey, PlayerId, ZoneName),
+    /// A card on the battlefield changed controller, from the first player to the second.
+    OnControlChanged(CardKey, PlayerId, PlayerId),
+    /// An attacker attacks a card, or a hero when None.
+    OnAttackDeclared(CardKey, Option<CardKey>),
+    /// A card dealt this much damage.
+    OnDamageDealt(CardKey, i32),
+    /// A card took this much damage.
+    OnDamageTaken(CardKey, i32),
+    /// The player's hero took this much damage.
+    OnHeroDamaged(PlayerId, i32),
+    /// The player's hero was healed by this much.
+    OnHeroHealed(PlayerId, i32),
+    /// A card was healed by this much.
+    OnCardHealed(CardKey, i32),
+    /// The player replaced this many cards in their mulligan.
+    OnMulligan(PlayerId, usize),
+    OnGameStart(),
+    OnGameEnd(GameResults),
+    OnTurnStart(PlayerId),
+    OnTurnEnd(PlayerId),
     OnBetweenTurns(),
 }
 
+/// The kind of a Trigger without any of its data. Used to register for events.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash)]
+pub enum TriggerKind {
+    OnCardDrawn,
+    AfterCardDrawn,
+    OnCardDrawFail,
+    OnCardBurned,
+    OnPlayCard,
+    OnCardPlayed,
+    OnCardDeath,
+    AfterCardDeath,
+    OnCardEnterZone,
+    OnCardLeaveZone,
+    OnControlChanged,
+    OnAttackDeclared,
+    OnDamageDealt,
+    OnDamageTaken,
+    OnHeroDamaged,
+    OnHeroHealed,
+    OnCardHealed,
+    OnMulligan,
+    OnGameStart,
+    OnGameEnd,
+    OnTurnStart,
+    OnTurnEnd,
+    OnBetweenTurns,
+}
+
 impl<'a> fmt::Debug for Trigger<'a> {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        match self {
-            Trigger::OnCardDrawn(_,_) => write!(f, "Trigger::OnCardDrawn"),
-            Trigger::AfterCardDrawn(_) => write!(f, "Trigger::OnCardDrawn"),
-            Trigger::OnCardDrawFail(_) => write!(f, "Trigger::OnCardDrawFail"),
-            Trigger::OnPlayCard(_,_,_) => write!(f, "Trigger::OnPlayCard"),
-            Trigger::OnTurnStart() => write!(f, "Trigger::OnTurnStart"),
-            Trigger::OnTurnEnd() => write!(f, "Trigger::OnTurnEnd"),
-            Trigger::OnBetweenTurns() => write!(f, "Trigger::OnBetweenTurns"),
-        }
+        write!(f, "Trigger::{:?}", self.kind())
     }
 }
 
 impl<'a> Trigger<'a> {
+    pub fn kind(&self) -> TriggerKind {
+        match self {
+            Trigger::OnCardDrawn(_, _) => TriggerKind::OnCardDrawn,
+            Trigger::AfterCardDrawn(_) => TriggerKind::AfterCardDrawn,
+            Trigger::OnCardDrawFail(_) => TriggerKind::OnCardDrawFail,
+            Trigger::OnCardBurned(_, _) => TriggerKind::OnCardBurned,
+            Trigger::OnPlayCard(_, _, _) => TriggerKind::OnPlayCard,
+            Trigger::OnCardPlayed(_, _) => TriggerKind::OnCardPlayed,
+            Trigger::OnCardDeath(_, _) => TriggerKind::OnCardDeath,
+            Trigger::AfterCardDeath(_) => TriggerKind::AfterCardDeath,
+            Trigger::OnCardEnterZone(_, _, _) => TriggerKind::OnCardEnterZone,
+            Trigger::OnCardLeaveZone(_, _, _) => TriggerKind::OnCardLeaveZone,
+            Trigger::OnControlChanged(_, _, _) => TriggerKind::OnControlChanged,
+            Trigger::OnAttackDeclared(_, _) => TriggerKind::OnAttackDeclared,
+            Trigger::OnDamageDealt(_, _) => TriggerKind::OnDamageDealt,
+            Trigger::OnDamageTaken(_, _) => TriggerKind::OnDamageTaken,
+            Trigger::OnHeroDamaged(_, _) => TriggerKind::OnHeroDamaged,
+            Trigger::OnHeroHealed(_, _) => TriggerKind::OnHeroHealed,
+            Trigger::OnCardHealed(_, _) => TriggerKind::OnCardHealed,
+            Trigger::OnMulligan(_, _) => TriggerKind::OnMulligan,
+            Trigger::OnGameStart() => TriggerKind::OnGameStart,
+            Trigger::OnGameEnd(_) => TriggerKind::OnGameEnd,
+            Trigger::OnTurnStart(_) => TriggerKind::OnTurnStart,
+            Trigger::OnTurnEnd(_) => TriggerKind::OnTurnEnd,
+            Trigger::OnBetweenTurns() => TriggerKind::OnBetweenTurns,
+        }
+    }
     pub fn cancelable(&self) -> bool {
         match self {
             Trigger::OnPlayCard(_, _, _) => true,
@@ -64,59 +142,127 @@ impl<'a> Trigger<'a> {
         }
     }
 }
-use std::sync::RwLock;
+pub type Callback = Box<dyn Fn(&mut Trigger, &mut GameHandle) + 'static + Sync + Send>;
 
-type Callback = Box<dyn Fn(&mut Trigger) + 'static + Sync + Send>;
-
-lazy_static! {
-    static ref INSTANCE: RwLock<Dispatch> = RwLock::new(Dispatch::new());
+/// A callback a card has registered for one kind of trigger.
+struct Listener {
+    card: CardKey,
+    owner: PlayerId,
+    priority: i32,
+    callback: Callback,
 }
+
+/// Sends triggers to the callbacks cards have registered. Each Game owns its own Dispatch.
+///
+/// Listeners are called in a fixed order: cards owned by the active player first,
+/// then higher priority first, then in the order the cards first registered (play order).
+#[derive(Default)]
 pub struct Dispatch {
-    trigger_callbacks: HashMap<u32, Callback>,
-    game: Option<Game>
+    listeners: HashMap<TriggerKind, Vec<Listener>>,
+    play_order: HashMap<CardKey, u64>,
+    next_play_order: u64,
+    active_player: PlayerId,
+    /// Changes to the game asked for by callbacks that have not been applied yet.
+    commands: Vec<ScriptCommand>,
 }
 impl Dispatch {
-    fn new() -> Dispatch {
-        Dispatch {
-            trigger_callbacks: HashMap::new(),
-            game: None,
-        }
+    pub fn new() -> Dispatch {
+        Dispatch::default()
     }
-    pub fn set_game(game: Game) -> Option<Game> {
-        let mut s = INSTANCE.write().unwrap();
-        let old = s.game.take();
-        s.game = Some(game);
+
+    /// Sets whose turn it is. Their cards are called first.
+    pub fn set_active_player(&mut self, player_id: PlayerId) {
+        self.active_player = player_id;
+    }
+
+    /// Registers the card's callback for a kind of trigger.
+    /// Replaces and returns the callback the card had for that kind.
+    pub fn register_event(
+        &mut self,
+        card: CardKey,
+        owner: PlayerId,
+        kind: TriggerKind,
+        priority: i32,
+        callback: Callback,
+    ) -> Option<Callback> {
+        let old = self.remove_event(card, kind);
+        if !self.play_order.contains_key(&card) {
+            self.play_order.insert(card, self.next_play_order);
+            self.next_play_order += 1;
+        }
+        self.listeners.entry(kind).or_default().push(Listener {
+            card,
+            owner,
+            priority,
+            callback,
+        });
         old
     }
 
-    // pub fn register_event(key: u32, callback: Callback) {
-    //     let mut s = INSTANCE.write().unwrap();
-    //     s.trigger_callbacks.insert(key, callback);
-    // }
+    /// Removes and returns the card's callback for a kind of trigger.
+    pub fn remove_event(&mut self, card: CardKey, kind: TriggerKind) -> Option<Callback> {
+        let list = self.listeners.get_mut(&kind)?;
+        let i = list.iter().position(|l| l.card == card)?;
+        Some(list.remove(i).callback)
+    }
+
+    /// Removes every callback the card has registered. Called when a card leaves play.
+    pub fn remove_all(&mut self, card: CardKey) {
+        for list in self.listeners.values_mut() {
+            list.retain(|l| l.card != card);
+        }
+        self.play_order.remove(&card);
+    }
 
-    // pub fn remove_event(key: u32) {
-    //     let mut s = INSTANCE.write().unwrap();
-    //     s.trigger_callbacks.remove(&key);
-    // }
+    /// Adds changes to be applied with the ones callbacks asked for.
+    pub fn queue(&mut self, mut commands: Vec<ScriptCommand>) {
+        self.commands.append(&mut commands);
+    }
+
+    /// Takes all changes callbacks have asked for so the Game can apply them.
+    pub fn take_commands(&mut self) -> Vec<ScriptCommand> {
+        std::mem::take(&mut self.commands)
+    }
 
-    pub fn broadcast(mut trigger: Trigger) {
+    /// The listeners for a kind of trigger in the order they are called.
+    fn ordered(&self, kind: TriggerKind) -> Vec<&Listener> {
+        let mut list: Vec<&Listener> = match self.listeners.get(&kind) {
+            Some(list) => list.iter().collect(),
+            None => return Vec::new(),
+        };
+        list.sort_by_key(|l| {
+            (
+                l.owner != self.active_player,
+                Reverse(l.priority),
+                self.play_order.get(&l.card).cloned().unwrap_or(u64::MAX),
+            )
+        });
+        list
+    }
+
+    /// Broadcasts that a card left one of the player's zones and entered another.
+    pub fn broadcast_move(&mut self, card: CardKey, player_id: PlayerId, from: ZoneName, to: ZoneName) {
+        self.broadcast(Trigger::OnCardLeaveZone(card, player_id, from));
+        self.broadcast(Trigger::OnCardEnterZone(card, player_id, to));
+    }
+
+    /// Sends the trigger to all callbacks. Returns true if the trigger was canceled.
+    /// Changes the callbacks ask for are kept until the Game takes them with take_commands.
+    pub fn broadcast(&mut self, mut trigger: Trigger) -> bool {
         debug!("Broadcasting {:?}", trigger);
         trigger.pre_broadcast();
-        if trigger.cancelable() {
-            for x in INSTANCE.read().unwrap().trigger_callbacks.iter() {
-                trace!("Broadcast callback for cancelable trigger:include_bytes! {}", x.0);
-                x.1(&mut trigger);
-                if trigger.is_canceled() {
-                    info!("Broadcast cancled on {}", x.0);
-                    break;
-                }
-            }
-        } else {
-            for x in INSTANCE.read().unwrap().trigger_callbacks.iter() {
-                trace!("Broadcast callback for trigger: {}", x.0);
-                x.1(&mut trigger);
+        let mut commands = Vec::new();
+        for listener in self.ordered(trigger.kind()) {
+            trace!("Broadcast callback for {:?}: {:?}", trigger, listener.card);
+            let mut handle = GameHandle::new(listener.card, listener.owner, &mut commands);
+            (listener.callback)(&mut trigger, &mut handle);
+            if trigger.is_canceled() {
+                info!("Broadcast canceled by {:?}", listener.card);
+                break;
             }
         }
+        self.commands.append(&mut commands);
         trigger.post_broadcast();
+        trigger.is_canceled()
     }
-}
\ No newline at end of file
+}
@@ -1 +1,47 @@
-pub struct HeroicWeapon{}
\ No newline at end of file
+use crate::entity::{TagKey, TagVal};
+use std::collections::HashMap;
+
+/// A weapon a hero can equip to attack with.
+/// Each attack uses one durability and the weapon breaks when none is left.
+#[derive(Clone, Debug)]
+pub struct HeroicWeapon {
+    name: String,
+    tags: HashMap<TagKey, TagVal>,
+}
+
+impl HeroicWeapon {
+    pub fn new(name: &str, attack: i32, durability: i32) -> HeroicWeapon {
+        let mut tags = HashMap::with_capacity(2);
+        tags.insert(TagKey::Attack, TagVal::from(attack));
+        tags.insert(TagKey::Durability, TagVal::from(durability));
+        HeroicWeapon {
+            name: String::from(name),
+            tags,
+        }
+    }
+
+    #[inline]
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn get_tag(&self, key: &TagKey) -> TagVal {
+        *self.tags.get(key).unwrap_or(&TagVal::None)
+    }
+    pub fn set_tag(&mut self, key: TagKey, val: TagVal) -> TagVal {
+        self.tags.insert(key, val).unwrap_or(TagVal::None)
+    }
+
+    pub fn attack(&self) -> i32 {
+        self.get_tag(&TagKey::Attack).as_i32()
+    }
+    pub fn durability(&self) -> i32 {
+        self.get_tag(&TagKey::Durability).as_i32()
+    }
+    /// Uses up one durability. Returns true if the weapon is now broken.
+    pub fn lose_durability(&mut self) -> bool {
+        let durability = self.durability() - 1;
+        self.set_tag(TagKey::Durability, TagVal::from(durability));
+        durability <= 0
+    }
+}
@@ -1,6 +1,11 @@
 use crate::entity::card::CardKey;
 pub use crate::game::action_result::{Error, OkCode, Result};
-use crate::game::{Deck, Game, Phase, PlayerId, Turn};
+use crate::game::combat;
+use crate::game::stack;
+use crate::game::target::{self, Target, TargetQuery};
+use crate::game::stage::GameResults;
+use crate::game::zones::ZoneName;
+use crate::game::{Deck, Game, NetPlayerId, Phase, PlayerId, Turn};
 use crate::net::Connection;
 use crate::utils::Input;
 use std::fmt;
@@ -30,7 +35,7 @@ impl fmt::Display for Actor {
     }
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub enum Action {
     Text(String),
     ChangePlayerId(PlayerId, PlayerId),
@@ -38,8 +43,37 @@ pub enum Action {
     Invalid,
     Error,
     Ok,
-    DrawCardKnown(usize, usize),
-    DrawCardAnon(usize, usize),
+    /// Sent from server when the player drew this card. Other players get DrawCardAnon.
+    DrawCardKnown(PlayerId, u64),
+    /// The player drew this many cards the receiver may not see.
+    DrawCardAnon(PlayerId, usize),
+    /// Sent from server when a card was put into one of the player's zones.
+    /// from is the player and zone it left, or None when it was just created.
+    /// card is None when the receiver may not see which card it is.
+    CardMoved {
+        card: Option<u64>,
+        player_id: PlayerId,
+        from: Option<(PlayerId, ZoneName)>,
+        to: ZoneName,
+    },
+    /// Sent from server with every card in one of the player's zones, from bottom to top.
+    /// Cards the receiver may not see are None.
+    ZoneContents {
+        player_id: PlayerId,
+        zone: ZoneName,
+        cards: Vec<Option<u64>>,
+    },
+    /// Sent from server with the public state of a player's hero and resources.
+    PlayerStatus {
+        player_id: PlayerId,
+        health: i32,
+        armor: i32,
+        resources: i32,
+        max_resources: i32,
+    },
+    /// Sent from server to a connection that joined as a spectator.
+    /// Spectators only get public information and can not act.
+    Spectating(NetPlayerId),
 
     /// Server responded with an Ok(OkCode)
     OnResponceOk(OkCode),
@@ -48,17 +82,46 @@ pub enum Action {
 
     // Player stated actions
     SelfEndTurn,
-    PlayCard(u64),
-    DirectAttack(u64, u64),
-    DeclareAttack(u64, u64),
+    /// Plays a card from hand: (card, target picked for its effects or Target::None)
+    PlayCard(CardKey, Target),
+    /// Attacker card attacks an enemy card or hero: (attacker, defender)
+    DeclareAttack(CardKey, Target),
+    /// The sender's hero attacks an enemy card or hero with its weapon.
+    HeroAttack(Target),
+    /// Asks the server what can be targeted. Answered with LegalTargets.
+    QueryTargets(TargetQuery),
+    /// The sender uses their hero's power.
+    UseHeroPower,
+    /// The sender gives up and loses the game.
+    Concede,
+    /// The sender does not respond to the top of the stack.
+    PassPriority,
 
     // Sent from core
     GameStart(),
-    MuliginStart(),
-    MuliginEnd(),
+    /// Sent from server when the game is over.
+    GameEnd(GameResults),
+    /// Sent from server with every target that would be accepted for the query.
+    LegalTargets(TargetQuery, Vec<Target>),
+    /// Sent from server when an effect is pushed onto the stack.
+    EffectPushed {
+        name: String,
+        owner: PlayerId,
+    },
+    /// Sent from server when the player may respond to the stack.
+    PriorityTo(PlayerId),
+    /// Sent from server when the top of the stack resolved.
+    EffectResolved {
+        name: String,
+    },
+    /// Sent from server with the cards in the player's starting hand.
+    MuliginStart(Vec<u64>),
+    /// Sent from server with the player's hand after the mulligan.
+    MuliginEnd(Vec<u64>),
     // from server/client
+    /// The cards in hand the player wants to replace. Empty to keep the hand.
     MuliginResult {
-        swap: bool,
+        swap: Vec<u64>,
     },
 
     /// Sent from server when all players are connected and game is being setup.
@@ -106,28 +169,42 @@ impl Action {
     fn server_perform(self, game: &mut Game, sender: &Actor) -> Result {
         match self {
             Action::ChangePlayerId(_from, _to) => Err(Error::NotSupported),
-            Action::EndTurn(p) => {
-                if sender.id() != p {
-                    warn!("Player ended the fro turn")
-                }
-                game.players[p].draw_x_cards(1);
+            Action::EndTurn(_p) => {
                 //game.queue_action(sender, Action::StartNextTurn());
                 Ok(OkCode::ChangeState)
             }
             Action::DrawCardAnon(pid, amount) => {
                 warn!("no drawing...");
-                //game.players[pid].draw_x_cards(amount);
+                //game.draw_cards(pid, amount);
                 //game.queue_action(sender, Action::EndTurn(sender));
                 Ok(OkCode::Continue)
             }
             Action::SetDeck(deck) => {
                 if deck.is_valid() {
-                    game.player(sender.id()).set_deck(deck);
+                    let player = &mut game.players[sender.id()];
+                    player.set_deck(deck, &mut game.cards, &mut game.rng)?;
                     Ok(OkCode::Done)
                 } else {
                     Err(Error::InvalidParamaters)
                 }
             }
+            Action::PlayCard(key, target) => game.play_card(sender.id(), key, target),
+            Action::DeclareAttack(attacker, target) => {
+                combat::declare_attack(game, sender.id(), attacker, target)
+            }
+            Action::QueryTargets(query) => {
+                let targets = target::legal_targets(game, sender.id(), query);
+                game.send_action(sender.id(), &Action::LegalTargets(query, targets))?;
+                Ok(OkCode::Done)
+            }
+            Action::HeroAttack(target) => combat::hero_attack(game, sender.id(), target),
+            Action::UseHeroPower => game.use_hero_power(sender.id()),
+            Action::MuliginResult { swap } => game.mulligan(sender.id(), &swap),
+            Action::Concede => {
+                game.player(sender.id()).concede();
+                Ok(OkCode::Done)
+            }
+            Action::PassPriority => stack::pass_priority(game, sender.id()),
             Action::GameStart() => Err(Error::NotSupported),
             Action::ReadyToPlay() => {
                 game.ready_players.insert(sender.id());
@@ -151,6 +228,84 @@ impl Action {
                 Ok(OkCode::Done)
             }
             Action::GameStart() => Ok(OkCode::Done),
+            Action::GameEnd(res) => {
+                info!("Game over: {:?}", res);
+                Ok(OkCode::Done)
+            }
+            Action::LegalTargets(query, targets) => {
+                info!("Legal targets for {:?}: {:?}", query, targets);
+                Ok(OkCode::Done)
+            }
+            Action::MuliginStart(hand) => {
+                info!("Starting hand: {:?}. Keeping all cards.", hand);
+                game.server().send(&Action::MuliginResult { swap: Vec::new() })?;
+                Ok(OkCode::Done)
+            }
+            Action::MuliginEnd(hand) => {
+                info!("Hand after mulligan: {:?}", hand);
+                Ok(OkCode::Done)
+            }
+            Action::EffectPushed { name, owner } => {
+                info!("Player #{} pushed {} onto the stack.", owner, name);
+                Ok(OkCode::Done)
+            }
+            Action::PriorityTo(player_id) => {
+                if player_id == game.local_player_id {
+                    info!("We may respond to the stack. Play an instant or 'resolve'.");
+                    Input::handle_input(sender.id(), game);
+                }
+                Ok(OkCode::Done)
+            }
+            Action::EffectResolved { name } => {
+                info!("{} resolved.", name);
+                Ok(OkCode::Done)
+            }
+            Action::Spectating(id) => {
+                info!("Watching the game as spectator #{}.", id);
+                game.local_player_id = id;
+                Ok(OkCode::Done)
+            }
+            Action::PlayerStatus {
+                player_id,
+                health,
+                armor,
+                resources,
+                max_resources,
+            } => {
+                info!(
+                    "Player #{} has {} health, {} armor and {}/{} resources.",
+                    player_id, health, armor, resources, max_resources
+                );
+                Ok(OkCode::Done)
+            }
+            Action::ZoneContents {
+                player_id,
+                zone,
+                cards,
+            } => {
+                info!("Player #{}'s {:?}: {:?}", player_id, zone, cards);
+                Ok(OkCode::Done)
+            }
+            Action::DrawCardKnown(player_id, card) => {
+                info!("Player #{} drew {}.", player_id, card);
+                Ok(OkCode::Done)
+            }
+            Action::DrawCardAnon(player_id, count) => {
+                info!("Player #{} drew {} hidden cards.", player_id, count);
+                Ok(OkCode::Done)
+            }
+            Action::CardMoved {
+                card,
+                player_id,
+                from,
+                to,
+            } => {
+                match card {
+                    Some(card) => info!("{} moved from {:?} to player #{}'s {:?}.", card, from, player_id, to),
+                    None => info!("A hidden card moved from {:?} to player #{}'s {:?}.", from, player_id, to),
+                }
+                Ok(OkCode::Done)
+            }
             Action::BeginGameSetup() => {
                 game.server().send(&Action::SetDeck(Deck::new()))?;
                 game.server().send(&Action::ReadyToPlay())?;
@@ -45,6 +45,10 @@ pub enum Error {
     InvalidParamaters,
     /// The action needed more parameters.
     MissingParamaters,
+    /// When a zone has no room for the card.
+    ZoneFull,
+    /// When the action is not allowed in the current state or phase.
+    WrongState,
 }
 
 impl Error {}
@@ -74,7 +78,11 @@ impl StdError for Error {
             Error::NoTarget => "No Target",
             Error::CantPayCost => "Can't Pay Cost",
             Error::NotSupported => "Not Supported",
-            _ => "Unknown Error",
+            Error::ZoneFull => "Zone Full",
+            Error::WrongState => "Not Allowed Now",
+            Error::NotFromRightPlayer => "Not From Right Player",
+            Error::InvalidParamaters => "Invalid Paramaters",
+            Error::MissingParamaters => "Missing Paramaters",
         }
     }
     fn cause(&self) -> Option<&StdError> {
@@ -1,10 +1,24 @@
 use crate::entity::{Card, CardKey, CardPool};
+use crate::game::zones::ZoneName;
+use crate::game::PlayerId;
 use crate::utils::vecmap::{IndexKey, IndexKeyAllocator, VecMap};
 
+/// Who a card belongs to and where it is.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct Placement {
+    /// The player whose deck the card came from, or who created it. Never changes.
+    pub owner: PlayerId,
+    /// The player whose zones hold the card. Differs from the owner after control changed.
+    pub controller: PlayerId,
+    /// The zone the card is in. None until the card is first put into a zone.
+    pub zone: Option<ZoneName>,
+}
+
 //#[derive(Clone, Default)]
 pub struct ActiveCardPool {
     idxalloc: IndexKeyAllocator,
     cards: VecMap<Card>,
+    placements: VecMap<Placement>,
 }
 
 impl ActiveCardPool {
@@ -12,16 +26,28 @@ impl ActiveCardPool {
         ActiveCardPool {
             idxalloc: IndexKeyAllocator::new(),
             cards: VecMap::new(),
+            placements: VecMap::new(),
         }
     }
 
-    pub fn push_new(&mut self, card_name: &str) -> CardKey {
+    /// Creates a card by name that belongs to the owner.
+    pub fn push_new(&mut self, card_name: &str, owner: PlayerId) -> CardKey {
         let key = self.idxalloc.allocate();
         self.cards.insert(key, Card::new(key, card_name));
+        self.placements.insert(key, Placement::new(owner));
         key
     }
+    /// Creates a copy of the card with its current base tags. The copy belongs to the owner.
+    pub fn push_copy(&mut self, key: CardKey, owner: PlayerId) -> Option<CardKey> {
+        let card = self.cards.get(key)?.copy();
+        let new_key = self.idxalloc.allocate();
+        self.cards.insert(new_key, card);
+        self.placements.insert(new_key, Placement::new(owner));
+        Some(new_key)
+    }
     pub fn remove(&mut self, key: CardKey) -> Option<Card> {
         if self.idxalloc.deallocate(key) {
+            self.placements.remove(key);
             self.cards.remove(key)
         } else {
             None
@@ -33,4 +59,39 @@ impl ActiveCardPool {
     pub fn get_mut(&mut self, key: CardKey) -> Option<&mut Card> {
         self.cards.get_mut(key)
     }
+    pub fn iter_mut(&mut self) -> impl Iterator<Item = &mut Card> {
+        self.cards.values_mut()
+    }
+
+    /// Who the card belongs to and where it is.
+    pub fn placement(&self, key: CardKey) -> Option<&Placement> {
+        self.placements.get(key)
+    }
+    pub fn owner(&self, key: CardKey) -> Option<PlayerId> {
+        self.placement(key).map(|p| p.owner)
+    }
+    pub fn controller(&self, key: CardKey) -> Option<PlayerId> {
+        self.placement(key).map(|p| p.controller)
+    }
+    pub fn zone_of(&self, key: CardKey) -> Option<ZoneName> {
+        self.placement(key).and_then(|p| p.zone)
+    }
+    /// Records that the card was put into one of the controller's zones.
+    /// Must be called every time a card moves so lookups match the players' zones.
+    pub fn set_zone(&mut self, key: CardKey, controller: PlayerId, zone: ZoneName) {
+        if let Some(placement) = self.placements.get_mut(key) {
+            placement.controller = controller;
+            placement.zone = Some(zone);
+        }
+    }
+}
+
+impl Placement {
+    fn new(owner: PlayerId) -> Placement {
+        Placement {
+            owner,
+            controller: owner,
+            zone: None,
+        }
+    }
 }
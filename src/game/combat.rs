@@ -0,0 +1,216 @@
+use crate::entity::{CardKey, TagKey, TagVal, Trigger};
+use crate::game::action_result::{Error, OkCode, Result};
+use crate::game::zones::ZoneName;
+use crate::game::{Game, PlayerId, Target};
+use std::result::Result as StdResult;
+
+/// Finds the player with this card on their battlefield, which is the player controlling it.
+pub fn battlefield_owner(game: &Game, key: CardKey) -> Option<PlayerId> {
+    game.cards
+        .placement(key)
+        .filter(|p| p.zone == Some(ZoneName::Battlefield))
+        .map(|p| p.controller)
+}
+
+/// Checks that the attacker is on the player's battlefield and can attack this turn.
+/// Returns the attacker's current attack.
+fn validate_attacker(game: &Game, player_id: PlayerId, attacker: CardKey) -> StdResult<i32, Error> {
+    if !game.players[player_id].zones.contains(ZoneName::Battlefield, attacker) {
+        return Err(Error::InvalidParamaters);
+    }
+    let card = game.cards.get(attacker).ok_or(Error::Internal)?;
+    if !card.can_attack() {
+        // No attack, just played without Charge, or out of attacks this turn.
+        return Err(Error::InvalidParamaters);
+    }
+    Ok(card.current_attack())
+}
+
+/// Checks that the attack is on a card with Taunt when the defending player has any.
+/// Cards with Stealth do not taunt.
+fn validate_taunt(game: &Game, defending_player: PlayerId, defender: Option<CardKey>) -> StdResult<(), Error> {
+    let taunts: Vec<CardKey> = game.players[defending_player]
+        .zones
+        .cards_in(ZoneName::Battlefield)
+        .iter()
+        .filter(|key| {
+            game.cards
+                .get(**key)
+                .is_some_and(|c| c.has(TagKey::Taunt) && !c.has(TagKey::Stealth))
+        })
+        .cloned()
+        .collect();
+    match defender {
+        _ if taunts.is_empty() => Ok(()),
+        Some(defender) if taunts.contains(&defender) => Ok(()),
+        _ => Err(Error::InvalidTarget),
+    }
+}
+
+/// Checks that the target is an enemy hero or an enemy card on the battlefield
+/// that can be attacked right now.
+pub fn validate_defender(game: &Game, player_id: PlayerId, target: Target) -> StdResult<(), Error> {
+    match target {
+        Target::Card(defender) => match battlefield_owner(game, defender) {
+            Some(owner) if owner != player_id => {
+                let card = game.cards.get(defender).ok_or(Error::Internal)?;
+                if card.has(TagKey::Stealth) {
+                    return Err(Error::InvalidTarget);
+                }
+                validate_taunt(game, owner, Some(defender))
+            }
+            Some(_) => Err(Error::InvalidTarget),
+            // The card exists but is not somewhere it can be attacked.
+            None if game.cards.get(defender).is_some() => Err(Error::InvalidTarget),
+            None => Err(Error::NoTarget),
+        },
+        Target::Hero(target) => {
+            validate_target_player(game, player_id, target)?;
+            validate_taunt(game, target, None)
+        }
+        Target::Zone(_, _) => Err(Error::InvalidTarget),
+        Target::None => Err(Error::NoTarget),
+    }
+}
+
+/// Checks that the player's attacker can attack the target.
+/// Returns the attacker's current attack.
+pub fn validate_attack(game: &Game, player_id: PlayerId, attacker: CardKey, target: Target) -> StdResult<i32, Error> {
+    let attack = validate_attacker(game, player_id, attacker)?;
+    validate_defender(game, player_id, target)?;
+    Ok(attack)
+}
+
+/// Player's attacker attacks an enemy card or hero.
+/// When a card is attacked both cards deal damage equal to their current attack to each other.
+pub fn declare_attack(game: &mut Game, player_id: PlayerId, attacker: CardKey, target: Target) -> Result {
+    let attack = validate_attack(game, player_id, attacker, target)?;
+    let defender = match target {
+        Target::Card(defender) => Some(defender),
+        _ => None,
+    };
+    game.dispatch.broadcast(Trigger::OnAttackDeclared(attacker, defender));
+    game.cards.get_mut(attacker).ok_or(Error::Internal)?.use_attack();
+
+    match target {
+        Target::Card(defender) => {
+            let counter_attack = game.cards.get(defender).map_or(0, |c| c.current_attack());
+            info!("{} attacked {} for {} damage", attacker.to_raw(), defender.to_raw(), attack);
+            deal_damage(game, Some(attacker), defender, attack);
+            deal_damage(game, Some(defender), attacker, counter_attack);
+        }
+        Target::Hero(target) => {
+            damage_hero(game, Some(attacker), target, attack);
+            info!(
+                "{} attacked player #{} directly for {} damage ({} health left)",
+                attacker.to_raw(),
+                target,
+                attack,
+                game.players[target].hero.health()
+            );
+        }
+        Target::Zone(_, _) | Target::None => (),
+    }
+    Ok(OkCode::Done)
+}
+
+/// Deals damage to a card and broadcasts it. Applies the source's Poisonous and Lifesteal.
+/// Returns the damage that was dealt.
+pub fn deal_damage(game: &mut Game, source: Option<CardKey>, target: CardKey, amount: i32) -> i32 {
+    let dealt = match game.cards.get_mut(target) {
+        Some(card) => card.take_damage(amount),
+        None => return 0,
+    };
+    if dealt <= 0 {
+        return 0;
+    }
+    if let Some(source) = source {
+        game.dispatch.broadcast(Trigger::OnDamageDealt(source, dealt));
+    }
+    game.dispatch.broadcast(Trigger::OnDamageTaken(target, dealt));
+    if source_has(game, source, TagKey::Poisonous) {
+        if let Some(card) = game.cards.get_mut(target) {
+            card.set_tag(TagKey::Destroyed, TagVal::from(true));
+        }
+    }
+    lifesteal(game, source, dealt);
+    dealt
+}
+
+/// Deals damage to a player's hero and broadcasts it. Applies the source's Lifesteal.
+/// Returns the damage that was dealt.
+pub fn damage_hero(game: &mut Game, source: Option<CardKey>, player_id: PlayerId, amount: i32) -> i32 {
+    let dealt = match game.players.get_mut(player_id) {
+        Some(player) => player.hero.take_damage(amount),
+        None => return 0,
+    };
+    if dealt <= 0 {
+        return 0;
+    }
+    if let Some(source) = source {
+        game.dispatch.broadcast(Trigger::OnDamageDealt(source, dealt));
+    }
+    game.dispatch.broadcast(Trigger::OnHeroDamaged(player_id, dealt));
+    lifesteal(game, source, dealt);
+    dealt
+}
+
+fn source_has(game: &Game, source: Option<CardKey>, key: TagKey) -> bool {
+    source
+        .and_then(|source| game.cards.get(source))
+        .is_some_and(|card| card.has(key))
+}
+
+/// Heals the hero of the source's controller by the damage dealt if the source has Lifesteal.
+/// The source does not have to be on the battlefield, so spells and dying minions heal too.
+fn lifesteal(game: &mut Game, source: Option<CardKey>, dealt: i32) {
+    if dealt <= 0 || !source_has(game, source, TagKey::Lifesteal) {
+        return;
+    }
+    let controller = source.and_then(|source| game.cards.controller(source));
+    if let Some(controller) = controller {
+        let healed = game.players[controller].hero.heal(dealt);
+        game.dispatch.broadcast(Trigger::OnHeroHealed(controller, healed));
+    }
+}
+
+/// Checks that target is an enemy player.
+fn validate_target_player(game: &Game, player_id: PlayerId, target: PlayerId) -> StdResult<PlayerId, Error> {
+    if target >= game.players.len() {
+        Err(Error::NoTarget)
+    } else if target == player_id {
+        Err(Error::InvalidTarget)
+    } else {
+        Ok(target)
+    }
+}
+
+/// Player's hero attacks an enemy card or hero with its weapon.
+/// An attacked card deals its attack back to the hero.
+pub fn hero_attack(game: &mut Game, player_id: PlayerId, target: Target) -> Result {
+    if !game.players[player_id].hero.can_attack() {
+        return Err(Error::InvalidParamaters);
+    }
+    validate_defender(game, player_id, target)?;
+    let attack = game.players[player_id].hero.use_attack();
+    match target {
+        Target::Card(defender) => {
+            let counter_attack = game.cards.get(defender).map_or(0, |c| c.current_attack());
+            info!("Player #{} attacked {} for {} damage", player_id, defender.to_raw(), attack);
+            deal_damage(game, None, defender, attack);
+            damage_hero(game, Some(defender), player_id, counter_attack);
+        }
+        Target::Hero(target) => {
+            damage_hero(game, None, target, attack);
+            info!(
+                "Player #{} attacked player #{} for {} damage ({} health left)",
+                player_id,
+                target,
+                attack,
+                game.players[target].hero.health()
+            );
+        }
+        Target::Zone(_, _) | Target::None => (),
+    }
+    Ok(OkCode::Done)
+}
@@ -1,15 +1,19 @@
+use crate::entity::{CardPool, HeroClass};
 use crate::game::zones::ZoneName;
 use std::fmt;
 
 #[derive(Clone, Deserialize, Serialize)]
 pub struct Deck {
     name: String,
+    #[serde(default)]
+    hero: HeroClass,
     cards: Vec<Entry>,
 }
 impl Deck {
     pub fn new() -> Deck {
         Deck {
             name: String::from("Example"),
+            hero: HeroClass::Warrior,
             cards: vec![
                 Entry::new("auto_gen_card_009", 1),
                 Entry::new("auto_gen_card_008", 2),
@@ -32,13 +36,31 @@ impl Deck {
     pub fn name(&self) -> &str {
         &self.name
     }
+    pub fn hero_class(&self) -> HeroClass {
+        self.hero
+    }
     pub fn is_valid(&self) -> bool {
-        self.cards.len() > 0
+        let count: usize = self.cards.iter().map(Entry::count).sum();
+        !self.cards.is_empty() && count <= ZoneName::Deck.max_size()
+    }
+    /// Names of all cards in this deck that are not in the CardPool.
+    pub fn unknown_cards(&self) -> Vec<&str> {
+        self.cards
+            .iter()
+            .map(Entry::card)
+            .filter(|name| CardPool::lookup_name(name).is_none())
+            .collect()
     }
 }
 impl fmt::Debug for Deck {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(f, "Deck {{ name: \"{}\", cards_len: {} }}", self.name, self.cards.len())
+        write!(
+            f,
+            "Deck {{ name: \"{}\", hero: {:?}, cards_len: {} }}",
+            self.name,
+            self.hero,
+            self.cards.len()
+        )
     }
 }
 
@@ -0,0 +1,231 @@
+use crate::entity::ability::{EffectDuration, Side, Target};
+use crate::entity::{CardKey, Effect, EffectNode, TagKey, TagVal};
+use crate::game::script::ScriptCommand;
+use crate::game::target::{self, TargetFilter, TargetKind};
+use crate::game::zones::ZoneName;
+use crate::game::{Game, PlayerId};
+use rand::seq::SliceRandom;
+
+/// Something an effect can be done to.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+enum Resolved {
+    Card(CardKey),
+    Hero(PlayerId),
+}
+
+/// Turns a card effect into the commands that carry it out, picking its targets now.
+pub fn resolve(
+    game: &mut Game,
+    source: CardKey,
+    owner: PlayerId,
+    effect: &EffectNode,
+) -> Vec<ScriptCommand> {
+    let mut commands = Vec::new();
+    match effect {
+        EffectNode::Damage(amount, target) => {
+            for target in targets(game, source, owner, *target) {
+                commands.push(match target {
+                    Resolved::Card(target) => ScriptCommand::DamageCard {
+                        source,
+                        target,
+                        amount: *amount,
+                    },
+                    Resolved::Hero(player_id) => ScriptCommand::DamageHero {
+                        source,
+                        player_id,
+                        amount: *amount,
+                    },
+                });
+            }
+        }
+        EffectNode::Heal(amount, target) => {
+            for target in targets(game, source, owner, *target) {
+                commands.push(match target {
+                    Resolved::Card(key) => ScriptCommand::HealCard(key, *amount),
+                    Resolved::Hero(player_id) => ScriptCommand::HealHero(player_id, *amount),
+                });
+            }
+        }
+        EffectNode::Keyword(tag, target) => {
+            for target in targets(game, source, owner, *target) {
+                if let Resolved::Card(key) = target {
+                    commands.push(ScriptCommand::SetTag(key, *tag, TagVal::from(true)));
+                }
+            }
+        }
+        EffectNode::TakeControl(target) => {
+            for target in targets(game, source, owner, *target) {
+                if let Resolved::Card(key) = target {
+                    commands.push(ScriptCommand::TakeControl(owner, key));
+                }
+            }
+        }
+        EffectNode::Draw(amount) => commands.push(ScriptCommand::DrawCards(owner, *amount)),
+        EffectNode::Summon(name) => commands.push(ScriptCommand::Summon(owner, name.clone())),
+        EffectNode::Create(name, zone) => commands.push(ScriptCommand::CreateCard {
+            player_id: owner,
+            name: name.clone(),
+            zone: *zone,
+        }),
+        EffectNode::Copy(target, zone) => {
+            for target in targets(game, source, owner, *target) {
+                if let Resolved::Card(key) = target {
+                    commands.push(ScriptCommand::CopyCard {
+                        player_id: owner,
+                        key,
+                        zone: *zone,
+                    });
+                }
+            }
+        }
+        EffectNode::GainArmor(amount) => commands.push(ScriptCommand::GainArmor(owner, *amount)),
+        EffectNode::Buff(tag, amount, target, duration) => {
+            let name = match game.cards.get(source) {
+                Some(card) => String::from(card.name()),
+                None => String::from("Unknown Card"),
+            };
+            let targets: Vec<Target> = match (target, duration) {
+                // Auras find their targets again whenever the board changes.
+                (Target::This, EffectDuration::WhileInPlay)
+                | (Target::AllMinions(_), EffectDuration::WhileInPlay) => {
+                    vec![*target]
+                }
+                _ => targets(game, source, owner, *target)
+                    .into_iter()
+                    .filter_map(|t| match t {
+                        Resolved::Card(key) => Some(Target::Card(key)),
+                        Resolved::Hero(_) => None,
+                    })
+                    .collect(),
+            };
+            for target in targets {
+                let node = EffectNode::Buff(*tag, *amount, target, *duration);
+                let effect = Effect::persistent(&name, source, owner, node);
+                commands.push(ScriptCommand::Persist(effect));
+            }
+        }
+        EffectNode::All(effects) => {
+            for effect in effects {
+                commands.append(&mut resolve(game, source, owner, effect));
+            }
+        }
+    }
+    commands
+}
+
+/// Drops persistent effects that no longer apply and recomputes every card's modifiers.
+/// Called whenever the board may have changed.
+pub fn update_persistent(game: &mut Game) {
+    let mut persistent = std::mem::take(&mut game.persistent);
+    persistent.retain(|effect| match (effect.duration(), effect.node()) {
+        (Some(EffectDuration::WhileInPlay), _) => in_play(game, effect.source()),
+        (Some(_), EffectNode::Buff(_, _, Target::Card(key), _)) => in_play(game, *key),
+        _ => false,
+    });
+
+    let alive: Vec<bool> = game
+        .cards
+        .iter_mut()
+        .map(|card| {
+            let alive = !card.is_dead();
+            card.clear_modifiers();
+            alive
+        })
+        .collect();
+    for effect in persistent.iter() {
+        if let EffectNode::Buff(tag, amount, target, _) = effect.node() {
+            let keys = match target {
+                Target::This => vec![effect.source()],
+                Target::Card(key) => vec![*key],
+                Target::AllMinions(side) => minions(game, effect.owner(), *side),
+                _ => Vec::new(),
+            };
+            for key in keys {
+                if let Some(card) = game.cards.get_mut(key) {
+                    card.add_modifier(*tag, *amount);
+                }
+            }
+        }
+    }
+    // Losing a health buff never kills a card, it only takes away the extra health.
+    for (card, alive) in game.cards.iter_mut().zip(alive) {
+        if alive {
+            card.keep_alive();
+        }
+    }
+    game.persistent = persistent;
+}
+
+/// Ends every buff that lasts until the end of the turn.
+pub fn end_turn(game: &mut Game) {
+    game.persistent
+        .retain(|effect| effect.duration() != Some(EffectDuration::EndOfTurn));
+}
+
+/// True if the card is on any battlefield.
+fn in_play(game: &Game, key: CardKey) -> bool {
+    game.cards.zone_of(key) == Some(ZoneName::Battlefield)
+}
+
+/// True if player_id is on the side, seen from owner.
+fn on_side(side: Side, owner: PlayerId, player_id: PlayerId) -> bool {
+    match side {
+        Side::Friendly => player_id == owner,
+        Side::Enemy => player_id != owner,
+        Side::Any => true,
+    }
+}
+
+/// Every minion on the battlefields of the players on the side.
+fn minions(game: &Game, owner: PlayerId, side: Side) -> Vec<CardKey> {
+    game.players
+        .iter()
+        .filter(|p| on_side(side, owner, p.player_id))
+        .flat_map(|p| p.zones.cards_in(ZoneName::Battlefield).iter().cloned())
+        .collect()
+}
+
+fn targets(game: &mut Game, source: CardKey, owner: PlayerId, target: Target) -> Vec<Resolved> {
+    match target {
+        // A card that has left play can not be targeted.
+        Target::This if in_play(game, source) => vec![Resolved::Card(source)],
+        Target::Card(key) if in_play(game, key) => vec![Resolved::Card(key)],
+        Target::This | Target::Card(_) => Vec::new(),
+        Target::Hero(side) => game
+            .players
+            .iter()
+            .filter(|p| on_side(side, owner, p.player_id))
+            .map(|p| Resolved::Hero(p.player_id))
+            .collect(),
+        // The target picked when the card was played, if it is still legal.
+        // Effects that were not played from hand get a legal target picked for them.
+        Target::ChosenMinion(side) => {
+            let filter = TargetFilter::new(side, TargetKind::Minion);
+            let legal: Vec<CardKey> = filter
+                .targets(game, owner)
+                .into_iter()
+                .filter_map(|t| match t {
+                    target::Target::Card(key) => Some(key),
+                    _ => None,
+                })
+                .collect();
+            let picked = match game.chosen_targets.get(&source) {
+                Some(target::Target::Card(key)) if legal.contains(key) => Some(*key),
+                Some(_) => None,
+                None => legal.choose(&mut game.rng).cloned(),
+            };
+            picked.map(|key| vec![Resolved::Card(key)]).unwrap_or_default()
+        }
+        Target::RandomMinion(side) => {
+            let minions = minions(game, owner, side);
+            minions
+                .choose(&mut game.rng)
+                .map(|key| vec![Resolved::Card(*key)])
+                .unwrap_or_default()
+        }
+        Target::AllMinions(side) => minions(game, owner, side)
+            .into_iter()
+            .map(Resolved::Card)
+            .collect(),
+    }
+}
@@ -1,41 +1,92 @@
 use crate::utils::timer::Timer;
-use crate::entity::{Card, Effect};
+use crate::entity::ability::{self, EffectDuration};
+use crate::entity::{
+    Card, CardKey, CardPool, Dispatch, Effect, EffectNode, HeroPower, HeroicWeapon, TagKey, TagVal,
+    Trigger,
+};
+use crate::game::combat;
+use crate::game::effects;
+use crate::game::target::{self, Target, TargetQuery};
+use crate::game::spectators::Spectators;
+use crate::game::visibility;
+use crate::game::script::{GameHandle, ScriptCommand};
+use crate::game::stack::{self, Priority};
+use crate::game::stage::GameResults;
+use crate::game::zones::{Location, ZoneName};
 use crate::game::action::Actor;
 use crate::game::{
-    Action, ActionResult, ActiveCardPool, Deck, OkCode, Player, PlayerId, Zone, ZoneCollection,
+    Action, ActionError, ActionResult, ActiveCardPool, Deck, GameRng, OkCode, Player, PlayerId,
+    Turn, Zone, ZoneCollection,
 };
 use crate::net::{Connection, NetError, NetResult, NetworkMode};
-use rand::{thread_rng, Rng};
+use rand::seq::SliceRandom;
+use rand::{thread_rng, Rng, SeedableRng};
 use serde::{Deserialize, Serialize};
 use std::collections::VecDeque;
 use std::collections::{HashMap, HashSet};
+use std::time::Duration;
+
+/// The number of cards each player draws before the mulligan.
+pub const STARTING_HAND_SIZE: usize = 5;
+/// How many times script commands may cause more script commands before the rest are dropped.
+/// Stops two scripts from triggering each other forever.
+const MAX_SCRIPT_ROUNDS: usize = 64;
 
 pub struct GameSettings {
     local_player_id: usize,
     max_players: usize,
     network_mode: NetworkMode,
+    seed: Option<u64>,
+    spectator_delay: Duration,
 }
 impl GameSettings {
     pub fn new(local_player_id: usize, max_players: usize, network_mode: NetworkMode) -> GameSettings {
         GameSettings {
             local_player_id, 
             max_players, 
-            network_mode
+            network_mode,
+            seed: None,
+            spectator_delay: Duration::from_millis(0),
         }
     }
+    /// Sets the seed for the game's RNG so the game can be replayed.
+    pub fn with_seed(mut self, seed: Option<u64>) -> GameSettings {
+        self.seed = seed;
+        self
+    }
+    /// Sets how long spectators wait before they see what happened.
+    pub fn with_spectator_delay(mut self, delay: Duration) -> GameSettings {
+        self.spectator_delay = delay;
+        self
+    }
 }
 
 pub struct Game {
     pub players: Vec<Player>,
     pub connections: Vec<Connection>,
+    /// Connections that watch the game without playing.
+    pub spectators: Spectators,
     active_player_id: PlayerId,
     pub local_player_id: usize,
     pub cards: ActiveCardPool,
     // action_queue: VecDeque<(PlayerId, Action)>,
+    /// Effects waiting to resolve. The back is the top of the stack.
     pub stack: VecDeque<Effect>,
+    /// Who may respond to the stack while it is not empty.
+    pub priority: Option<Priority>,
+    /// Buffs and auras that modify cards on the board.
+    pub persistent: Vec<Effect>,
+    /// The target each card in play was given when it was played.
+    pub chosen_targets: HashMap<CardKey, Target>,
     network_mode: NetworkMode,
     pub ready_players: HashSet<PlayerId>,
+    /// Players that have not finished their mulligan.
+    pub mulligan_pending: HashSet<PlayerId>,
     pub timer: Timer,
+    pub rng: GameRng,
+    seed: u64,
+    /// Sends triggers to the cards in this game.
+    pub dispatch: Dispatch,
 }
 
 impl Game {
@@ -63,17 +114,28 @@ impl Game {
             }
         }
 
+        let seed = settings.seed.unwrap_or_else(|| thread_rng().gen());
+        info!("Game RNG seed: {}", seed);
+
         Game {
             players,
             connections,
+            spectators: Spectators::new(settings.spectator_delay),
             active_player_id: 0,
             local_player_id: settings.local_player_id,
             cards: ActiveCardPool::new(),
             stack: VecDeque::new(),
+            priority: None,
+            persistent: Vec::new(),
+            chosen_targets: HashMap::new(),
             // action_queue: VecDeque::new(),
             network_mode: settings.network_mode,
             ready_players: HashSet::new(),
+            mulligan_pending: HashSet::new(),
             timer: Timer::default(),
+            rng: GameRng::seed_from_u64(seed),
+            seed,
+            dispatch: Dispatch::new(),
         }
     }
     /// Gets which of Server, Client, or Both that this game is running as.
@@ -81,6 +143,11 @@ impl Game {
         self.network_mode
     }
 
+    /// The seed the game's RNG was created with.
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
     pub fn has_authority(&self) -> bool {
         self.network_mode.is_server()
     }
@@ -103,7 +170,8 @@ impl Game {
         &mut self.players[id]
     }
     pub fn set_active_player(&mut self, player_id: usize) {
-        self.active_player_id = player_id
+        self.active_player_id = player_id;
+        self.dispatch.set_active_player(player_id);
     }
     pub fn active_player_id(&self) -> usize {
         self.active_player_id
@@ -134,32 +202,215 @@ impl Game {
     }
 
     pub fn shuffle_decks(&mut self) {
-        let mut rng = thread_rng();
-        for p in self.players() {
-            p.set_deck(crate::game::Deck::new());
-            let mut deck = p.zones.deck.as_mut_slice();
-            // TODO rng.shuffle(&mut deck);
+        for p in self.players.iter_mut() {
+            p.zones.shuffle(ZoneName::Deck, &mut self.rng);
+        }
+    }
+
+    /// The player draws x cards from the top of their deck.
+    /// Cards drawn with a full hand are burned and drawing from an empty deck causes fatigue.
+    pub fn draw_cards(&mut self, player_id: PlayerId, x: usize) {
+        let player = &mut self.players[player_id];
+        let drawn_cards = player
+            .zones
+            .remove_x_at(ZoneName::Deck, x, Location::Top, &mut self.rng);
+
+        for c in drawn_cards {
+            match c {
+                Some(key) => {
+                    let mut card = self.cards.get_mut(key);
+                    if let Some(card) = card.as_mut() {
+                        card.on_card_drawn(&mut self.dispatch, player);
+                    }
+                    let res = player
+                        .zones
+                        .insert_at(ZoneName::Hand, Location::Top, key, &mut self.rng);
+                    match (res, card) {
+                        (Err(key), _) => {
+                            info!("Player '{}' has a full hand. Card was burned.", player.name);
+                            let graveyard = ZoneName::Graveyard;
+                            if player.zones.insert_at(graveyard, Location::Top, key, &mut self.rng).is_err() {
+                                warn!("Player '{}' has a full graveyard.", player.name);
+                            }
+                            self.cards.set_zone(key, player_id, ZoneName::Graveyard);
+                            let (from, to) = (ZoneName::Deck, ZoneName::Graveyard);
+                            let action = Action::CardMoved {
+                                card: Some(key.to_raw()),
+                                player_id,
+                                from: Some((player_id, from)),
+                                to,
+                            };
+                            visibility::send_all(&self.connections, &action).unwrap_or(());
+                            self.spectators.send_all(&action);
+                            self.dispatch.broadcast_move(key, player_id, from, to);
+                            self.dispatch.broadcast(Trigger::OnCardBurned(player, key));
+                        }
+                        (Ok(()), card) => {
+                            let (from, to) = (ZoneName::Deck, ZoneName::Hand);
+                            self.dispatch.broadcast_move(key, player_id, from, to);
+                            if let Some(card) = card {
+                                card.after_card_drawn(&mut self.dispatch, player);
+                            }
+                            self.cards.set_zone(key, player_id, ZoneName::Hand);
+                            let action = Action::DrawCardKnown(player_id, key.to_raw());
+                            visibility::send_all(&self.connections, &action).unwrap_or(());
+                            self.spectators.send_all(&action);
+                        }
+                    }
+                }
+                None => {
+                    self.dispatch.broadcast(Trigger::OnCardDrawFail(player));
+                    let damage = player.take_fatigue();
+                    self.dispatch.broadcast(Trigger::OnHeroDamaged(player_id, damage));
+                }
+            }
+        }
+    }
+
+    /// Each player draws their starting hand.
+    pub fn deal_starting_hands(&mut self) {
+        for player_id in 0..self.players.len() {
+            self.draw_cards(player_id, STARTING_HAND_SIZE);
+        }
+    }
+
+    /// Sends each player their starting hand and waits for their mulligan.
+    pub fn start_mulligan(&mut self) {
+        self.mulligan_pending = self.players.iter().map(|p| p.player_id).collect();
+        for player_id in 0..self.players.len() {
+            let hand = self.hand_keys(player_id);
+            self.send_action(player_id, &Action::MuliginStart(hand))
+                .unwrap_or(());
+        }
+    }
+
+    /// Replaces the selected cards in the player's hand with cards from the top of their deck.
+    /// The replaced cards are put back into the deck at random locations.
+    pub fn mulligan(&mut self, player_id: PlayerId, swap: &[u64]) -> ActionResult {
+        if !self.mulligan_pending.contains(&player_id) {
+            return Err(ActionError::InvalidParamaters);
+        }
+        let keys: Vec<CardKey> = swap
+            .iter()
+            .map(|raw| CardKey::from_raw(*raw))
+            .collect::<Option<_>>()
+            .ok_or(ActionError::InvalidParamaters)?;
+
+        let player = &mut self.players[player_id];
+        if keys.len() > player.zones.len(ZoneName::Deck) {
+            return Err(ActionError::InvalidParamaters);
+        }
+        for (i, key) in keys.iter().enumerate() {
+            if !player.zones.contains(ZoneName::Hand, *key) || keys[..i].contains(key) {
+                return Err(ActionError::InvalidParamaters);
+            }
+        }
+
+        for key in keys.iter() {
+            player.zones.remove(ZoneName::Hand, *key);
+        }
+        self.draw_cards(player_id, keys.len());
+        info!("Player #{} replaced {} cards.", player_id, keys.len());
+        for key in keys.iter() {
+            let (from, to) = (ZoneName::Hand, ZoneName::Deck);
+            self.dispatch.broadcast_move(*key, player_id, from, to);
+        }
+        let count = keys.len();
+        for key in keys.iter() {
+            self.cards.set_zone(*key, player_id, ZoneName::Deck);
+            self.send_move(*key, player_id, Some((player_id, ZoneName::Hand)), ZoneName::Deck);
+        }
+        let res = self.players[player_id]
+            .zones
+            .insert_all_at(ZoneName::Deck, Location::Random, keys, &mut self.rng);
+        if let Err(left) = res {
+            warn!("Player #{} has a full deck. {} cards were lost.", player_id, left.len());
+        }
+        self.dispatch.broadcast(Trigger::OnMulligan(player_id, count));
+
+        self.finish_mulligan(player_id);
+        if self.mulligan_pending.is_empty() {
+            Ok(OkCode::ChangeState)
+        } else {
+            Ok(OkCode::Done)
+        }
+    }
+
+    /// Ends the mulligan. Players that have not chosen keep their starting hand.
+    pub fn end_mulligan(&mut self) {
+        let pending: Vec<PlayerId> = self.mulligan_pending.iter().cloned().collect();
+        for player_id in pending {
+            info!("Player #{} kept their hand.", player_id);
+            self.finish_mulligan(player_id);
         }
     }
 
-    pub fn run_mulligan(&mut self) {
-        for p in self.players() {
-            p.draw_x_cards(5);
+    /// Sends the player their hand after the mulligan.
+    fn finish_mulligan(&mut self, player_id: PlayerId) {
+        if self.mulligan_pending.remove(&player_id) {
+            let hand = self.hand_keys(player_id);
+            self.send_action(player_id, &Action::MuliginEnd(hand))
+                .unwrap_or(());
         }
     }
 
+    fn hand_keys(&self, player_id: PlayerId) -> Vec<u64> {
+        self.players[player_id]
+            .zones
+            .cards_in(ZoneName::Hand)
+            .iter()
+            .map(|k| k.to_raw())
+            .collect()
+    }
+
+    /// Sends a game action to every player and spectator, hiding what each of them may not see.
     pub fn send_all_action(&mut self, action: &Action) -> NetResult<()> {
-        for conn in self.connections() {
-            match conn.send(action) {
-                Ok(_) => (),
-                Err(e) => return Err(e),
+        self.spectators.send_all(action);
+        visibility::send_all(&self.connections, action)
+    }
+    /// Lets a spectator watch the game from now on.
+    /// It is first sent each player's hero and resources, the contents of every zone,
+    /// with the cards it may not see left out, and the current turn.
+    pub fn add_spectator(&mut self, conn: Connection, turn: Option<Turn>) {
+        let mut snapshot = Vec::new();
+        for player in self.players.iter() {
+            snapshot.push(Action::PlayerStatus {
+                player_id: player.player_id,
+                health: player.hero.health(),
+                armor: player.hero.armor(),
+                resources: player.resources.current,
+                max_resources: player.resources.max,
+            });
+            for zone in ZoneName::ALL.iter() {
+                let cards = player.zones.cards_in(*zone);
+                if cards.is_empty() {
+                    continue;
+                }
+                snapshot.push(Action::ZoneContents {
+                    player_id: player.player_id,
+                    zone: *zone,
+                    cards: cards.iter().map(|k| Some(k.to_raw())).collect(),
+                });
             }
         }
-        Ok(())
+        if let Some(turn) = turn {
+            snapshot.push(Action::SwitchTurn(turn));
+        }
+        self.spectators.join(conn, snapshot);
     }
-    // Sends a game action to the player over their connection.
+    // Sends a game action to the player over their connection, hiding what they may not see.
     pub fn send_action(&mut self, client_id: usize, action: &Action) -> NetResult<()> {
-        self.connection(client_id).send(action)
+        visibility::send(&self.connections[client_id], action)
+    }
+    /// Tells every player a card moved into the player's zone, or was created when from is None.
+    fn send_move(&mut self, key: CardKey, player_id: PlayerId, from: Option<(PlayerId, ZoneName)>, to: ZoneName) {
+        let action = Action::CardMoved {
+            card: Some(key.to_raw()),
+            player_id,
+            from,
+            to,
+        };
+        self.send_all_action(&action).unwrap_or(());
     }
 
     /// Returns true when a state changse is needed.
@@ -184,4 +435,473 @@ impl Game {
     }
 
     pub fn process_triggers(&mut self) {}
+
+    /// Checks if the game has been won, lost or drawn.
+    /// Returns None while two or more players are still alive.
+    pub fn check_game_over(&self) -> Option<GameResults> {
+        let mut alive = self.players.iter().filter(|p| p.is_alive());
+        match (alive.next(), alive.next()) {
+            (None, _) => Some(GameResults::Draw),
+            (Some(winner), None) => Some(GameResults::PlayerWin(winner.player_id)),
+            (Some(_), Some(_)) => None,
+        }
+    }
+
+    /// Player pays for and uses their hero's power.
+    pub fn use_hero_power(&mut self, player_id: PlayerId) -> ActionResult {
+        let player = &mut self.players[player_id];
+        let power = player.hero.hero_power();
+        if !player.hero.can_use_hero_power() {
+            return Err(ActionError::InvalidParamaters);
+        }
+        if !player.resources.pay(power.cost()) {
+            return Err(ActionError::CantPayCost);
+        }
+        player.hero.set_hero_power_used();
+        info!("Player #{} used {:?}.", player_id, power);
+
+        match power {
+            HeroPower::None => (),
+            HeroPower::ArmorUp => player.hero.gain_armor(2),
+            HeroPower::DaggerMastery => {
+                player.hero.equip(HeroicWeapon::new("Wicked Knife", 1, 2));
+            }
+            HeroPower::LesserHeal => {
+                let healed = player.hero.heal(2);
+                self.dispatch.broadcast(Trigger::OnHeroHealed(player_id, healed));
+            }
+            HeroPower::SteadyShot => {
+                for enemy in self.players.iter_mut().filter(|p| p.player_id != player_id) {
+                    let damage = enemy.hero.take_damage(2);
+                    let trigger = Trigger::OnHeroDamaged(enemy.player_id, damage);
+                    self.dispatch.broadcast(trigger);
+                }
+            }
+        }
+        Ok(OkCode::Done)
+    }
+
+    /// Starts the player's turn and readies the cards on their battlefield to attack.
+    pub fn start_turn(&mut self, player_id: PlayerId) {
+        let player = &mut self.players[player_id];
+        player.start_turn();
+        for key in player.zones.cards_in(ZoneName::Battlefield).iter() {
+            if let Some(card) = self.cards.get_mut(*key) {
+                card.start_turn();
+            }
+        }
+    }
+
+    /// Player pays for a card in their hand and moves it to the battlefield.
+    /// The target is used by the card's effects that let the player pick one.
+    pub fn play_card(&mut self, player_id: PlayerId, key: CardKey, target: Target) -> ActionResult {
+        let legal = target::legal_targets(self, player_id, TargetQuery::PlayCard(key));
+        if !legal.contains(&target) {
+            // A card that needs a target can still be played when there is nothing to pick.
+            let nothing_to_pick = legal.is_empty() && target == Target::None;
+            if !nothing_to_pick {
+                return Err(match target {
+                    Target::None => ActionError::NoTarget,
+                    _ => ActionError::InvalidTarget,
+                });
+            }
+        }
+        let player = &mut self.players[player_id];
+        if !player.zones.contains(ZoneName::Hand, key) {
+            return Err(ActionError::InvalidParamaters);
+        }
+        if player.zones.is_full(ZoneName::Battlefield) {
+            return Err(ActionError::ZoneFull);
+        }
+        let card = self.cards.get_mut(key).ok_or(ActionError::Internal)?;
+        let cost = card.current_cost();
+        if !player.resources.can_pay(cost) {
+            return Err(ActionError::CantPayCost);
+        }
+        if self.dispatch.broadcast(Trigger::OnPlayCard(player, card, false)) {
+            info!("Playing {} was canceled.", card);
+            return Ok(OkCode::Skip);
+        }
+
+        player.resources.pay(cost);
+        card.enter_play();
+        player.zones.remove(ZoneName::Hand, key);
+        player
+            .zones
+            .insert_at(ZoneName::Battlefield, Location::Default, key, &mut self.rng)
+            .map_err(|_| ActionError::ZoneFull)?;
+        info!("Player #{} played {} for {}.", player_id, card, cost);
+        self.cards.set_zone(key, player_id, ZoneName::Battlefield);
+        self.send_move(key, player_id, Some((player_id, ZoneName::Hand)), ZoneName::Battlefield);
+        self.chosen_targets.insert(key, target);
+        // Registered first so the card's own script hears it enter play.
+        self.register_script(player_id, key);
+        let (from, to) = (ZoneName::Hand, ZoneName::Battlefield);
+        self.dispatch.broadcast_move(key, player_id, from, to);
+        self.dispatch.broadcast(Trigger::OnCardPlayed(key, player_id));
+        Ok(OkCode::Done)
+    }
+
+    /// Registers the card's script for each kind of trigger it wants.
+    /// Called when a card enters play.
+    pub fn register_script(&mut self, player_id: PlayerId, key: CardKey) {
+        let card = match self.cards.get(key) {
+            Some(card) => card,
+            None => return,
+        };
+        let priority = card.script().priority();
+        for kind in card.script().triggers() {
+            let script = card.script().box_clone();
+            let callback = Box::new(move |trigger: &mut Trigger, game: &mut GameHandle| {
+                let owner = game.card();
+                script.on_event(trigger, owner, game)
+            });
+            self.dispatch
+                .register_event(key, player_id, *kind, priority, callback);
+        }
+    }
+
+    /// Creates a new token by name on the player's battlefield.
+    /// Returns None if there is no such card or no room for it.
+    pub fn summon(&mut self, player_id: PlayerId, name: &str) -> Option<CardKey> {
+        self.create_card(player_id, name, ZoneName::Battlefield)
+    }
+
+    /// Creates a token by name in one of the player's zones.
+    /// Returns None if there is no such card or no room for it.
+    pub fn create_card(&mut self, player_id: PlayerId, name: &str, zone: ZoneName) -> Option<CardKey> {
+        if CardPool::lookup_name(name).is_none() {
+            warn!("Can not create unknown card '{}'.", name);
+            return None;
+        }
+        if self.players.get(player_id)?.zones.is_full(zone) {
+            return None;
+        }
+        let key = self.cards.push_new(name, player_id);
+        self.place_token(player_id, key, zone)
+    }
+
+    /// Creates a token copy of a card, with its current base tags, in one of the player's zones.
+    /// Permanent buffs on the card become part of the copy's base tags.
+    /// Returns None if the card does not exist or there is no room for it.
+    pub fn copy_card(&mut self, player_id: PlayerId, key: CardKey, zone: ZoneName) -> Option<CardKey> {
+        if self.players.get(player_id)?.zones.is_full(zone) {
+            return None;
+        }
+        let copy = self.cards.push_copy(key, player_id)?;
+        for effect in self.persistent.iter() {
+            if effect.duration() != Some(EffectDuration::Permanent) {
+                continue;
+            }
+            if let EffectNode::Buff(tag, amount, ability::Target::Card(target), _) = effect.node() {
+                if *target == key {
+                    if let Some(card) = self.cards.get_mut(copy) {
+                        let value = card.get_base_tag(tag).as_i32() + amount;
+                        card.set_tag(*tag, TagVal::from(value));
+                    }
+                }
+            }
+        }
+        self.place_token(player_id, copy, zone)
+    }
+
+    /// Marks a new card as a token and puts it in the zone.
+    fn place_token(&mut self, player_id: PlayerId, key: CardKey, zone: ZoneName) -> Option<CardKey> {
+        let card = self.cards.get_mut(key)?;
+        card.set_tag(TagKey::Token, TagVal::from(true));
+        let location = match zone {
+            ZoneName::Deck => Location::Random,
+            ZoneName::Battlefield => {
+                card.enter_play();
+                Location::Default
+            }
+            _ => Location::Default,
+        };
+        info!("Player #{} created {} in their {:?}.", player_id, card, zone);
+        let player = &mut self.players[player_id];
+        if player.zones.insert_at(zone, location, key, &mut self.rng).is_err() {
+            self.cards.remove(key);
+            return None;
+        }
+        self.cards.set_zone(key, player_id, zone);
+        self.send_move(key, player_id, None, zone);
+        if zone == ZoneName::Battlefield {
+            self.register_script(player_id, key);
+        }
+        self.dispatch
+            .broadcast(Trigger::OnCardEnterZone(key, player_id, zone));
+        Some(key)
+    }
+
+    /// Moves a card from another player's battlefield to the player's battlefield.
+    /// The card can not attack until the player's next turn. Its owner does not change.
+    /// Returns false if the card is not on an enemy battlefield or there is no room for it.
+    pub fn take_control(&mut self, player_id: PlayerId, key: CardKey) -> bool {
+        let from = match combat::battlefield_owner(self, key) {
+            Some(from) if from != player_id => from,
+            _ => return false,
+        };
+        match self.players.get(player_id) {
+            Some(player) if !player.zones.is_full(ZoneName::Battlefield) => (),
+            _ => return false,
+        }
+        self.players[from].zones.remove(ZoneName::Battlefield, key);
+        let player = &mut self.players[player_id];
+        if player
+            .zones
+            .insert_at(ZoneName::Battlefield, Location::Default, key, &mut self.rng)
+            .is_err()
+        {
+            return false;
+        }
+        self.cards.set_zone(key, player_id, ZoneName::Battlefield);
+        self.send_move(key, player_id, Some((from, ZoneName::Battlefield)), ZoneName::Battlefield);
+        if let Some(card) = self.cards.get_mut(key) {
+            card.enter_play();
+            info!("Player #{} took control of {}.", player_id, card);
+        }
+        // The card's triggers and auras now belong to its new controller.
+        self.dispatch.remove_all(key);
+        self.register_script(player_id, key);
+        for effect in self.persistent.iter_mut().filter(|e| e.source() == key) {
+            effect.set_owner(player_id);
+        }
+        self.dispatch
+            .broadcast(Trigger::OnControlChanged(key, from, player_id));
+        true
+    }
+
+    /// Applies everything scripts have asked for, including what those changes trigger in turn.
+    pub fn resolve_scripts(&mut self) {
+        for _ in 0..MAX_SCRIPT_ROUNDS {
+            let commands = self.dispatch.take_commands();
+            if commands.is_empty() {
+                return;
+            }
+            for command in commands {
+                self.apply_command(command);
+            }
+        }
+        let dropped = self.dispatch.take_commands();
+        warn!("Scripts kept triggering each other. Dropped {} commands.", dropped.len());
+    }
+
+    pub fn apply_command(&mut self, command: ScriptCommand) {
+        debug!("Applying {:?}", command);
+        match command {
+            ScriptCommand::DamageCard {
+                source,
+                target,
+                amount,
+            } => {
+                combat::deal_damage(self, Some(source), target, amount);
+            }
+            ScriptCommand::DamageHero {
+                source,
+                player_id,
+                amount,
+            } => {
+                combat::damage_hero(self, Some(source), player_id, amount);
+            }
+            ScriptCommand::HealHero(player_id, amount) => {
+                if let Some(player) = self.players.get_mut(player_id) {
+                    let healed = player.hero.heal(amount);
+                    self.dispatch.broadcast(Trigger::OnHeroHealed(player_id, healed));
+                }
+            }
+            ScriptCommand::HealCard(key, amount) => {
+                if let Some(card) = self.cards.get_mut(key) {
+                    let healed = card.heal(amount);
+                    if healed > 0 {
+                        self.dispatch.broadcast(Trigger::OnCardHealed(key, healed));
+                    }
+                }
+            }
+            ScriptCommand::GainArmor(player_id, amount) => {
+                if let Some(player) = self.players.get_mut(player_id) {
+                    player.hero.gain_armor(amount);
+                }
+            }
+            ScriptCommand::DrawCards(player_id, amount) => {
+                if player_id < self.players.len() {
+                    self.draw_cards(player_id, amount);
+                }
+            }
+            ScriptCommand::Summon(player_id, name) => {
+                self.summon(player_id, &name);
+            }
+            ScriptCommand::CreateCard {
+                player_id,
+                name,
+                zone,
+            } => {
+                self.create_card(player_id, &name, zone);
+            }
+            ScriptCommand::CopyCard {
+                player_id,
+                key,
+                zone,
+            } => {
+                self.copy_card(player_id, key, zone);
+            }
+            ScriptCommand::SetTag(key, tag, val) => {
+                if let Some(card) = self.cards.get_mut(key) {
+                    card.set_tag(tag, val);
+                }
+            }
+            ScriptCommand::TakeControl(player_id, key) => {
+                self.take_control(player_id, key);
+            }
+            ScriptCommand::RunEffect {
+                source,
+                owner,
+                effect,
+            } => {
+                let name = match self.cards.get(source) {
+                    Some(card) => String::from(card.name()),
+                    None => String::from("Unknown Card"),
+                };
+                stack::push(self, Effect::new(&name, source, owner, effect));
+            }
+            ScriptCommand::ResolveEffect {
+                source,
+                owner,
+                effect,
+            } => {
+                for command in effects::resolve(self, source, owner, &effect) {
+                    self.apply_command(command);
+                }
+            }
+            ScriptCommand::Persist(effect) => {
+                debug!("{} now modifies the board.", effect.name());
+                self.persistent.push(effect);
+            }
+            ScriptCommand::Inspect { card, owner, kind } => {
+                let script = match self.cards.get(card) {
+                    Some(c) => c.script().box_clone(),
+                    None => return,
+                };
+                let mut commands = Vec::new();
+                let mut handle = GameHandle::new(card, owner, &mut commands);
+                script.on_inspect(kind, self, &mut handle);
+                // Applied in the next round so scripts that keep inspecting can not loop forever.
+                self.dispatch.queue(commands);
+            }
+        }
+    }
+
+    /// State-based cleanup that runs after every resolved action.
+    /// Applies what scripts asked for and moves each card on a battlefield with no health left
+    /// to its graveyard, until nothing else happens.
+    /// Returns the number of cards that died.
+    pub fn run_cleanup(&mut self) -> usize {
+        let mut count = 0;
+        loop {
+            self.resolve_scripts();
+            effects::update_persistent(self);
+            let died = self.destroy_dead();
+            if died == 0 {
+                return count;
+            }
+            count += died;
+        }
+    }
+
+    /// Moves each dead card on a battlefield to its owner's graveyard.
+    fn destroy_dead(&mut self) -> usize {
+        let mut count = 0;
+        for player_id in 0..self.players.len() {
+            let cards = &self.cards;
+            let dead: Vec<CardKey> = self.players[player_id]
+                .zones
+                .cards_in(ZoneName::Battlefield)
+                .iter()
+                .filter(|key| cards.get(**key).is_some_and(Card::is_dead))
+                .cloned()
+                .collect();
+
+            for key in dead {
+                self.players[player_id].zones.remove(ZoneName::Battlefield, key);
+                let owner = self.cards.owner(key).unwrap_or(player_id);
+                let mut token = false;
+                if let Some(card) = self.cards.get_mut(key) {
+                    token = card.has(TagKey::Token);
+                    card.on_card_death(&mut self.dispatch, &mut self.players[player_id]);
+                    let from = ZoneName::Battlefield;
+                    self.dispatch
+                        .broadcast(Trigger::OnCardLeaveZone(key, player_id, from));
+                    if token {
+                        // Tokens are removed from the game instead of going to the graveyard.
+                        info!("{} died and was removed from the game.", card);
+                    } else {
+                        let zones = &mut self.players[owner].zones;
+                        if zones.insert_at(ZoneName::Graveyard, Location::Top, key, &mut self.rng).is_err() {
+                            warn!("Player #{} has a full graveyard.", owner);
+                        }
+                        self.dispatch
+                            .broadcast(Trigger::OnCardEnterZone(key, owner, ZoneName::Graveyard));
+                        info!("{} died and was put in the graveyard.", card);
+                    }
+                    card.after_card_death(&mut self.dispatch, &mut self.players[player_id]);
+                }
+                if !token {
+                    self.cards.set_zone(key, owner, ZoneName::Graveyard);
+                    self.send_move(key, owner, Some((player_id, ZoneName::Battlefield)), ZoneName::Graveyard);
+                }
+                // Cards stop listening for triggers once they leave play.
+                self.dispatch.remove_all(key);
+                self.chosen_targets.remove(&key);
+                if token {
+                    // Clients are told the token was banished so they stop showing it.
+                    self.send_move(key, owner, Some((player_id, ZoneName::Battlefield)), ZoneName::Banished);
+                    self.cards.remove(key);
+                }
+                count += 1;
+            }
+        }
+        count
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A server game with the seed, where every deck holds the same 30 cards before it is shuffled.
+    /// Returns each player's deck and hand after shuffling and dealing starting hands.
+    fn deal(seed: u64) -> Vec<(Vec<CardKey>, Vec<CardKey>)> {
+        let settings = GameSettings::new(0, 2, NetworkMode::Server).with_seed(Some(seed));
+        let mut game = Game::new(&settings);
+        for player_id in 0..game.players.len() {
+            let keys = (1..=30)
+                .map(|i| CardKey::from_raw((1 << 32) | (player_id as u64 * 100 + i)).unwrap())
+                .collect();
+            game.players[player_id]
+                .zones
+                .insert_all_at(ZoneName::Deck, Location::Default, keys, &mut game.rng)
+                .unwrap();
+        }
+        game.shuffle_decks();
+        game.deal_starting_hands();
+        game.players
+            .iter()
+            .map(|p| {
+                let deck = p.zones.cards_in(ZoneName::Deck).to_vec();
+                let hand = p.zones.cards_in(ZoneName::Hand).to_vec();
+                (deck, hand)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn same_seed_deals_the_same_game() {
+        let first = deal(42);
+        assert_eq!(first, deal(42));
+        assert!(first.iter().all(|(_, hand)| hand.len() == STARTING_HAND_SIZE));
+    }
+
+    #[test]
+    fn different_seeds_deal_different_games() {
+        assert_ne!(deal(42), deal(43));
+    }
 }
@@ -3,12 +3,18 @@ pub mod game_state;
 pub mod action;
 pub mod action_result;
 pub mod active_card_pool;
+pub mod combat;
 // pub mod core;
 pub mod deck;
+pub mod effects;
 pub mod player;
 pub mod script;
+pub mod spectators;
+pub mod stack;
 pub mod stage;
+pub mod target;
 pub mod turn;
+pub mod visibility;
 pub mod zones;
 
 pub use self::action::Action;
@@ -19,6 +25,7 @@ pub use self::game_state::{Game,GameSettings};
 pub use self::player::Player;
 pub use self::script::GameScript;
 pub use self::stage::Stage;
+pub use self::target::Target;
 pub use self::turn::{Phase, Turn};
 pub use self::zones::Zone;
 pub use self::zones::ZoneCollection;
@@ -31,3 +38,5 @@ pub type NetPlayerId = usize;
 pub type PlayerId = usize;
 pub type ClientId = usize;
 pub type CardId = usize;
+/// The random number generator used for all game logic.
+pub type GameRng = rand::rngs::StdRng;
@@ -1,7 +1,37 @@
-use crate::entity::{Dispatch, Trigger};
+use crate::config;
+use crate::entity::{Hero, HeroClass};
 use crate::game::zones::{Location, Zone, ZoneName};
 use crate::game::ZoneCollection;
-use crate::game::{Deck, PlayerId};
+use crate::game::{ActionError, ActiveCardPool, Deck, GameRng, PlayerId};
+
+/// The most resources a player can have at once.
+pub const MAX_RESOURCES: i32 = 10;
+
+/// A player's pool of resources that is spent to play cards.
+#[derive(Clone, Copy, Debug, Default, Serialize, Deserialize)]
+pub struct Resources {
+    pub current: i32,
+    pub max: i32,
+}
+impl Resources {
+    /// Gains one max resource (up to MAX_RESOURCES) then refills current to max.
+    pub fn refill(&mut self) {
+        self.max = Ord::min(self.max + 1, MAX_RESOURCES);
+        self.current = self.max;
+    }
+    pub fn can_pay(&self, cost: i32) -> bool {
+        cost <= self.current
+    }
+    /// Spends cost from current. Returns false and spends nothing if it can not be paid.
+    pub fn pay(&mut self, cost: i32) -> bool {
+        if self.can_pay(cost) {
+            self.current -= Ord::max(cost, 0);
+            true
+        } else {
+            false
+        }
+    }
+}
 
 // This is the players reprsentation in the game.
 // Player owns the cards and the moves.
@@ -11,6 +41,11 @@ pub struct Player {
     pub name: String,
     pub deck: Option<Deck>,
     pub zones: ZoneCollection,
+    pub resources: Resources,
+    pub hero: Hero,
+    pub conceded: bool,
+    /// Number of times this player has drawn from an empty deck.
+    pub fatigue: i32,
 }
 
 impl Player {
@@ -19,7 +54,11 @@ impl Player {
             player_id,
             name,
             deck: None,
-            zones: ZoneCollection::new(42),
+            zones: ZoneCollection::new(player_id),
+            resources: Resources::default(),
+            hero: Hero::new(HeroClass::Neutral),
+            conceded: false,
+            fatigue: 0,
         }
     }
 
@@ -33,6 +72,25 @@ impl Player {
         None
     }
 
+    /// Called when this player's turn enters Phase::Start.
+    pub fn start_turn(&mut self) {
+        self.resources.refill();
+        self.hero.start_turn();
+        info!(
+            "Player '{}' has {}/{} resources.",
+            self.name, self.resources.current, self.resources.max
+        );
+    }
+
+    /// True while this player can still win the game.
+    pub fn is_alive(&self) -> bool {
+        !self.conceded && !self.hero.is_dead()
+    }
+    pub fn concede(&mut self) {
+        info!("Player '{}' has conceded.", self.name);
+        self.conceded = true;
+    }
+
     pub fn zones(&self) -> &ZoneCollection {
         &self.zones
     }
@@ -40,35 +98,56 @@ impl Player {
         &mut self.zones
     }
 
-    // Sets the players starting deck and fills, return the old startng deck if one exists.
-    pub fn set_deck(&mut self, deck: Deck) {
-        let zone = self.zones.get_mut(ZoneName::Deck);
+    /// Sets the players starting deck and fills the deck zone with a new card for each entry.
+    /// Any cards from an old starting deck are removed from the game.
+    /// Fails and changes nothing if the deck has cards that are not in the CardPool
+    /// or has more cards than fit in the deck zone.
+    pub fn set_deck(
+        &mut self,
+        deck: Deck,
+        cards: &mut ActiveCardPool,
+        rng: &mut GameRng,
+    ) -> Result<(), ActionError> {
+        let unknown = deck.unknown_cards();
+        if !unknown.is_empty() {
+            warn!("Player '{}' deck has unknown cards: {:?}", self.name, unknown);
+            return Err(ActionError::InvalidParamaters);
+        }
+        let size: usize = deck
+            .cards_for_zone(ZoneName::Deck)
+            .iter()
+            .map(|entry| entry.count())
+            .sum();
+        if size > ZoneName::Deck.max_size() {
+            warn!("Player '{}' deck has {} cards, more than fit in a deck.", self.name, size);
+            return Err(ActionError::ZoneFull);
+        }
+
+        for key in self.zones.clear(ZoneName::Deck) {
+            cards.remove(key);
+        }
         for entry in deck.cards_for_zone(ZoneName::Deck) {
             for _ in 0..entry.count() {
-                //TODO zone.insert_at(Location::Default, CardPool::make_card(entry.card()));
+                let key = cards.push_new(entry.card(), self.player_id);
+                self.zones
+                    .insert_at(ZoneName::Deck, Location::Default, key, rng)
+                    .map_err(|_| ActionError::ZoneFull)?;
+                cards.set_zone(key, self.player_id, ZoneName::Deck);
             }
         }
+        info!("Player '{}' deck has {} cards.", self.name, self.zones.len(ZoneName::Deck));
+        self.hero = Hero::new(deck.hero_class());
         self.deck = Some(deck);
+        Ok(())
     }
 
-    pub fn draw_x_cards(&mut self, x: usize) {
-        if x == 0 {
-            return;
-        }
-
-        let drawn_cards = self.zones.deck.remove_x_at(x, Location::Top);
-
-        for c in drawn_cards {
-            match c {
-                Some(mut card) => {
-                    //TODO card.on_card_drawn(self);
-                    self.zones.hand.insert_at(Location::Top, card);
-                    //card_moved.after_card_drawn(self);
-                }
-                None => {
-                    Dispatch::broadcast(Trigger::OnCardDrawFail(self));
-                }
-            }
-        }
+    /// Deals escalating damage to this player's hero for drawing from an empty deck.
+    /// Returns the damage dealt.
+    pub fn take_fatigue(&mut self) -> i32 {
+        let settings = config::active();
+        let damage = settings.fatigue_start + self.fatigue * settings.fatigue_increment;
+        self.fatigue += 1;
+        info!("Player '{}' takes {} fatigue damage.", self.name, damage);
+        self.hero.take_damage(damage)
     }
 }
@@ -1,5 +1,8 @@
+use crate::entity::ability::When;
+use crate::entity::{Ability, CardKey, Effect, EffectNode, TagKey, TagVal, Trigger, TriggerKind};
+use crate::game::zones::ZoneName;
+use crate::game::{Game, PlayerId};
 use std::collections::HashMap;
-use std::fmt;
 
 lazy_static! {
     static ref INSTANCE: HashMap<&'static str, Script> = {
@@ -8,6 +11,8 @@ lazy_static! {
         m.insert("0", ExampleScript { num: 43 }.box_clone() );
         m.insert("ten", Box::new(ExampleScript2 { name: String::from("ten") }) );
         m.insert("banana", Box::new(ExampleScript { num: 0 }));
+        m.insert("draw_on_turn_start", Box::new(DrawOnTurnStart));
+        m.insert("ping_on_enemy_turn_end", Box::new(PingOnEnemyTurnEnd));
         m
     };
 }
@@ -29,11 +34,247 @@ impl ScriptManager {
 }
 pub type Script = Box<dyn GameScript>;
 
+/// A change to the game a script asked for.
+/// They are applied by the Game after the broadcast that caused them is done.
+#[derive(Debug, Clone)]
+pub enum ScriptCommand {
+    /// Deal damage from the source card to the target card.
+    DamageCard {
+        source: CardKey,
+        target: CardKey,
+        amount: i32,
+    },
+    /// Deal damage from the source card to the player's hero.
+    DamageHero {
+        source: CardKey,
+        player_id: PlayerId,
+        amount: i32,
+    },
+    HealHero(PlayerId, i32),
+    HealCard(CardKey, i32),
+    GainArmor(PlayerId, i32),
+    DrawCards(PlayerId, usize),
+    /// Create a card by name on the player's battlefield.
+    Summon(PlayerId, String),
+    /// Create a token by name in one of the player's zones.
+    CreateCard {
+        player_id: PlayerId,
+        name: String,
+        zone: ZoneName,
+    },
+    /// Create a token copy of a card in one of the player's zones.
+    CopyCard {
+        player_id: PlayerId,
+        key: CardKey,
+        zone: ZoneName,
+    },
+    SetTag(CardKey, TagKey, TagVal),
+    /// Move a card on a battlefield to the player's battlefield.
+    TakeControl(PlayerId, CardKey),
+    /// Run a card effect. Its targets are picked when it is applied.
+    RunEffect {
+        source: CardKey,
+        owner: PlayerId,
+        effect: EffectNode,
+    },
+    /// Resolve a card effect right away instead of putting it on the stack.
+    ResolveEffect {
+        source: CardKey,
+        owner: PlayerId,
+        effect: EffectNode,
+    },
+    /// Keep a buff on the board.
+    Persist(Effect),
+    /// Call the card's script again with a read-only view of the game.
+    Inspect {
+        card: CardKey,
+        owner: PlayerId,
+        kind: TriggerKind,
+    },
+}
+
+/// What a script may do to the game while it handles a trigger.
+/// Scripts never touch the Game directly, every change is queued and applied in order.
+/// Scripts that need to read the game ask for it with inspect.
+pub struct GameHandle<'a> {
+    card: CardKey,
+    owner: PlayerId,
+    commands: &'a mut Vec<ScriptCommand>,
+}
+impl<'a> GameHandle<'a> {
+    pub fn new(card: CardKey, owner: PlayerId, commands: &'a mut Vec<ScriptCommand>) -> Self {
+        GameHandle {
+            card,
+            owner,
+            commands,
+        }
+    }
+    /// The card whose script is running.
+    #[inline]
+    pub fn card(&self) -> CardKey {
+        self.card
+    }
+    /// The player who owns the card whose script is running.
+    #[inline]
+    pub fn owner(&self) -> PlayerId {
+        self.owner
+    }
+    /// Deals damage from this card to the target card.
+    pub fn deal_damage(&mut self, target: CardKey, amount: i32) {
+        self.commands.push(ScriptCommand::DamageCard {
+            source: self.card,
+            target,
+            amount,
+        });
+    }
+    pub fn damage_hero(&mut self, player_id: PlayerId, amount: i32) {
+        self.commands.push(ScriptCommand::DamageHero {
+            source: self.card,
+            player_id,
+            amount,
+        });
+    }
+    pub fn heal_hero(&mut self, player_id: PlayerId, amount: i32) {
+        self.commands.push(ScriptCommand::HealHero(player_id, amount));
+    }
+    pub fn draw_cards(&mut self, player_id: PlayerId, amount: usize) {
+        self.commands.push(ScriptCommand::DrawCards(player_id, amount));
+    }
+    /// Summons a token by card name for the owner of this card.
+    pub fn summon(&mut self, name: &str) {
+        self.commands
+            .push(ScriptCommand::Summon(self.owner, String::from(name)));
+    }
+    /// Creates a token by name in one of the owner's zones.
+    pub fn create_card(&mut self, name: &str, zone: ZoneName) {
+        self.commands.push(ScriptCommand::CreateCard {
+            player_id: self.owner,
+            name: String::from(name),
+            zone,
+        });
+    }
+    /// Creates a token copy of a card in one of the owner's zones.
+    pub fn copy_card(&mut self, key: CardKey, zone: ZoneName) {
+        self.commands.push(ScriptCommand::CopyCard {
+            player_id: self.owner,
+            key,
+            zone,
+        });
+    }
+    pub fn set_tag(&mut self, card: CardKey, key: TagKey, val: TagVal) {
+        self.commands.push(ScriptCommand::SetTag(card, key, val));
+    }
+    /// This card's owner takes control of a card on a battlefield.
+    pub fn take_control(&mut self, card: CardKey) {
+        self.commands.push(ScriptCommand::TakeControl(self.owner, card));
+    }
+    /// Applies an aura for this card and its owner without using the stack.
+    pub fn add_aura(&mut self, effect: &EffectNode) {
+        self.commands.push(ScriptCommand::ResolveEffect {
+            source: self.card,
+            owner: self.owner,
+            effect: effect.clone(),
+        });
+    }
+    /// Asks for GameScript::on_inspect to be called with a read-only view of the game
+    /// once the current trigger is done. kind tells the script why it asked.
+    pub fn inspect(&mut self, kind: TriggerKind) {
+        self.commands.push(ScriptCommand::Inspect {
+            card: self.card,
+            owner: self.owner,
+            kind,
+        });
+    }
+    /// Runs a card effect for this card and its owner.
+    pub fn run_effect(&mut self, effect: &EffectNode) {
+        self.commands.push(ScriptCommand::RunEffect {
+            source: self.card,
+            owner: self.owner,
+            effect: effect.clone(),
+        });
+    }
+}
+
 pub trait GameScript: Send + Sync {
-    /// Any event has happened. WIP
-    fn on_event(&self);
+    /// The kinds of triggers this script wants to hear about once its card is in play.
+    fn triggers(&self) -> &[TriggerKind] {
+        &[]
+    }
+    /// Scripts with a higher priority are called first.
+    fn priority(&self) -> i32 {
+        0
+    }
+    /// A trigger this script registered for has happened.
+    /// owner is the card the script belongs to.
+    fn on_event(&self, trigger: &mut Trigger, owner: CardKey, game: &mut GameHandle);
+    /// Called after the script asked for it with GameHandle::inspect.
+    /// The script can read anything in the game, changes are still queued on the handle.
+    fn on_inspect(&self, _kind: TriggerKind, _game: &Game, _handle: &mut GameHandle) {}
     /// Create a new version of the script and return it in a box.
-    fn box_clone(&self) -> Box<GameScript>;
+    fn box_clone(&self) -> Box<dyn GameScript>;
+}
+
+/// Runs the abilities parsed from a card's effect text.
+pub struct AbilityScript {
+    abilities: Vec<Ability>,
+    kinds: Vec<TriggerKind>,
+}
+impl AbilityScript {
+    pub fn new(abilities: &[Ability]) -> AbilityScript {
+        let mut kinds = Vec::new();
+        for ability in abilities {
+            let kind = match ability.when {
+                When::Play => TriggerKind::OnCardPlayed,
+                When::Aura => TriggerKind::OnCardEnterZone,
+                When::Death => TriggerKind::OnCardLeaveZone,
+                When::TurnStart => TriggerKind::OnTurnStart,
+                When::TurnEnd => TriggerKind::OnTurnEnd,
+                When::Damaged => TriggerKind::OnDamageTaken,
+            };
+            if !kinds.contains(&kind) {
+                kinds.push(kind);
+            }
+        }
+        AbilityScript {
+            abilities: abilities.to_vec(),
+            kinds,
+        }
+    }
+
+    /// True if the trigger is the moment the ability happens for this card.
+    fn happens(when: When, trigger: &Trigger, card: CardKey, player_id: PlayerId) -> bool {
+        match (when, trigger) {
+            (When::Play, Trigger::OnCardPlayed(key, _)) => *key == card,
+            (When::Aura, Trigger::OnCardEnterZone(key, _, ZoneName::Battlefield)) => *key == card,
+            (When::Death, Trigger::OnCardLeaveZone(key, _, ZoneName::Battlefield)) => *key == card,
+            (When::TurnStart, Trigger::OnTurnStart(p)) => *p == player_id,
+            (When::TurnEnd, Trigger::OnTurnEnd(p)) => *p == player_id,
+            (When::Damaged, Trigger::OnDamageTaken(key, _)) => *key == card,
+            _ => false,
+        }
+    }
+}
+impl GameScript for AbilityScript {
+    fn triggers(&self) -> &[TriggerKind] {
+        &self.kinds
+    }
+    fn on_event(&self, trigger: &mut Trigger, owner: CardKey, game: &mut GameHandle) {
+        for ability in self.abilities.iter() {
+            if !Self::happens(ability.when, trigger, owner, game.owner()) {
+                continue;
+            }
+            match ability.when {
+                When::Aura => game.add_aura(&ability.effect),
+                _ => game.run_effect(&ability.effect),
+            }
+        }
+    }
+    fn box_clone(&self) -> Box<dyn GameScript> {
+        Box::new(AbilityScript {
+            abilities: self.abilities.clone(),
+            kinds: self.kinds.clone(),
+        })
+    }
 }
 
 struct ExampleScript {
@@ -43,29 +284,78 @@ struct ExampleScript2 {
     pub name: String,
 }
 impl GameScript for ExampleScript {
-    fn on_event(&self) {
-        println!("ok script #{}", self.num)
+    fn triggers(&self) -> &[TriggerKind] {
+        &[TriggerKind::OnTurnStart]
     }
-    fn box_clone(&self) -> Box<GameScript> {
+    fn on_event(&self, _trigger: &mut Trigger, owner: CardKey, _game: &mut GameHandle) {
+        debug!("Example script #{} for {:?}", self.num, owner);
+    }
+    fn box_clone(&self) -> Box<dyn GameScript> {
         Box::new(ExampleScript { num: self.num })
     }
 }
 impl GameScript for ExampleScript2 {
-    fn on_event(&self) {
-        println!("ok script named '{}'", self.name)
+    fn triggers(&self) -> &[TriggerKind] {
+        &[TriggerKind::OnTurnEnd]
+    }
+    fn on_event(&self, _trigger: &mut Trigger, _owner: CardKey, _game: &mut GameHandle) {
+        debug!("Example script named '{}'", self.name);
     }
-    fn box_clone(&self) -> Box<GameScript> {
+    fn box_clone(&self) -> Box<dyn GameScript> {
         Box::new(ExampleScript2 {
             name: self.name.clone(),
         })
     }
 }
 
+/// At the start of its owner's turn, draws them a card unless their hand is full.
+struct DrawOnTurnStart;
+impl GameScript for DrawOnTurnStart {
+    fn triggers(&self) -> &[TriggerKind] {
+        &[TriggerKind::OnTurnStart]
+    }
+    fn on_event(&self, trigger: &mut Trigger, _owner: CardKey, game: &mut GameHandle) {
+        if let Trigger::OnTurnStart(player_id) = trigger {
+            if *player_id == game.owner() {
+                game.inspect(trigger.kind());
+            }
+        }
+    }
+    fn on_inspect(&self, _kind: TriggerKind, game: &Game, handle: &mut GameHandle) {
+        // Only draws when the card would not be burned.
+        let owner = handle.owner();
+        if !game.players[owner].zones.is_full(ZoneName::Hand) {
+            handle.draw_cards(owner, 1);
+        }
+    }
+    fn box_clone(&self) -> Box<dyn GameScript> {
+        Box::new(DrawOnTurnStart)
+    }
+}
+
+/// At the end of each enemy's turn, deals 1 damage to their hero.
+struct PingOnEnemyTurnEnd;
+impl GameScript for PingOnEnemyTurnEnd {
+    fn triggers(&self) -> &[TriggerKind] {
+        &[TriggerKind::OnTurnEnd]
+    }
+    fn on_event(&self, trigger: &mut Trigger, _owner: CardKey, game: &mut GameHandle) {
+        if let Trigger::OnTurnEnd(player_id) = trigger {
+            if *player_id != game.owner() {
+                game.damage_hero(*player_id, 1);
+            }
+        }
+    }
+    fn box_clone(&self) -> Box<dyn GameScript> {
+        Box::new(PingOnEnemyTurnEnd)
+    }
+}
+
 impl GameScript for () {
-    fn on_event(&self) {
+    fn on_event(&self, _trigger: &mut Trigger, _owner: CardKey, _game: &mut GameHandle) {
         info!("ok script empty");
     }
-    fn box_clone(&self) -> Box<GameScript> {
+    fn box_clone(&self) -> Box<dyn GameScript> {
         Box::new(())
     }
 }
@@ -0,0 +1,118 @@
+//! Spectators watch a game without playing in it. They are sent the same actions as
+//! the players with only public information left, optionally after a delay.
+use crate::game::visibility;
+use crate::game::{Action, NetPlayerId};
+use crate::net::Connection;
+use std::sync::mpsc::{channel, Receiver, Sender};
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// Something the feed thread does once its time comes.
+enum FeedEvent {
+    Join(Connection),
+    Leave(NetPlayerId),
+    /// Sent to one spectator. Used to catch up a spectator that joined mid-game.
+    SendTo(NetPlayerId, Action),
+    SendAll(Action),
+    /// Disconnects every spectator and stops the feed.
+    Close,
+}
+
+/// The spectators of one game.
+/// Actions are sent from a feed thread so delayed actions go out on time without holding up the game.
+pub struct Spectators {
+    feed: Option<Sender<(Instant, FeedEvent)>>,
+    ids: Vec<NetPlayerId>,
+    delay: Duration,
+}
+
+impl Spectators {
+    pub fn new(delay: Duration) -> Spectators {
+        Spectators {
+            feed: None,
+            ids: Vec::new(),
+            delay,
+        }
+    }
+
+    /// True if the connection with this id is a spectator.
+    pub fn contains(&self, id: NetPlayerId) -> bool {
+        self.ids.contains(&id)
+    }
+
+    /// Adds a spectator. It is sent the snapshot first so it can watch from any point in the game.
+    pub fn join(&mut self, conn: Connection, snapshot: Vec<Action>) {
+        let id = conn.player_id();
+        if self.feed.is_none() {
+            let (send, recv) = channel();
+            let builder = thread::Builder::new().name(String::from("spectator_feed"));
+            if let Err(e) = builder.spawn(move || run_feed(recv)) {
+                warn!("Unable to start the spectator feed: {}", e);
+                return;
+            }
+            self.feed = Some(send);
+        }
+        info!("Spectator #{} joined.", id);
+        self.ids.push(id);
+        self.push(FeedEvent::Join(conn));
+        for action in snapshot {
+            self.push(FeedEvent::SendTo(id, action));
+        }
+    }
+
+    pub fn leave(&mut self, id: NetPlayerId) {
+        info!("Spectator #{} left.", id);
+        self.ids.retain(|i| *i != id);
+        self.push(FeedEvent::Leave(id));
+    }
+
+    /// Sends the action to every spectator once the delay has passed.
+    pub fn send_all(&mut self, action: &Action) {
+        if !self.ids.is_empty() {
+            self.push(FeedEvent::SendAll(action.clone()));
+        }
+    }
+
+    /// Disconnects every spectator after they have been sent everything.
+    pub fn close(&mut self) {
+        self.ids.clear();
+        self.push(FeedEvent::Close);
+        self.feed = None;
+    }
+
+    fn push(&mut self, event: FeedEvent) {
+        if let Some(feed) = self.feed.as_ref() {
+            feed.send((Instant::now() + self.delay, event)).unwrap_or(());
+        }
+    }
+}
+
+/// Handles each event when its time comes, in the order they were pushed.
+fn run_feed(recv: Receiver<(Instant, FeedEvent)>) {
+    let mut conns: Vec<Connection> = Vec::new();
+    for (due, event) in recv {
+        let now = Instant::now();
+        if due > now {
+            thread::sleep(due - now);
+        }
+        match event {
+            FeedEvent::Join(conn) => conns.push(conn),
+            FeedEvent::Leave(id) => conns.retain(|c| c.player_id() != id),
+            FeedEvent::SendTo(id, action) => {
+                for conn in conns.iter().filter(|c| c.player_id() == id) {
+                    visibility::send(conn, &action).unwrap_or(());
+                }
+            }
+            FeedEvent::SendAll(action) => {
+                // One spectator that went away does not stop the others from watching.
+                for conn in conns.iter() {
+                    visibility::send(conn, &action).unwrap_or(());
+                }
+            }
+            FeedEvent::Close => break,
+        }
+    }
+    for conn in conns.iter_mut() {
+        conn.disconnect();
+    }
+}
@@ -0,0 +1,144 @@
+//! The effect stack. Played cards and triggered abilities push effects onto it,
+//! every player gets a chance to respond, and then effects resolve last in first out.
+use crate::entity::{Effect, TagKey};
+use crate::game::action_result::{Error, OkCode, Result};
+use crate::game::effects;
+use crate::game::zones::ZoneName;
+use crate::game::{Action, Game, PlayerId};
+use std::time::{Duration, Instant};
+
+/// How many effects may resolve at once without waiting before the rest are dropped.
+/// Stops effects that keep pushing more effects from running forever.
+const MAX_RESOLVE: usize = 256;
+/// How long a player may hold priority before they pass without answering.
+const PRIORITY_TIME: Duration = Duration::from_secs(15);
+
+/// The player who may respond to the stack and how many players passed in a row.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Priority {
+    pub holder: PlayerId,
+    passes: usize,
+    expires: Instant,
+}
+impl Priority {
+    /// How long the holder has left to respond.
+    pub fn time_left(&self) -> Duration {
+        self.expires.saturating_duration_since(Instant::now())
+    }
+}
+
+/// Pushes an effect onto the stack and gives the next player a chance to respond to it.
+pub fn push(game: &mut Game, effect: Effect) {
+    info!("{} pushed onto the stack by player #{}.", effect.name(), effect.owner());
+    let action = Action::EffectPushed {
+        name: String::from(effect.name()),
+        owner: effect.owner(),
+    };
+    game.send_all_action(&action).unwrap_or(());
+    let next = next_player(game, effect.owner());
+    game.stack.push_back(effect);
+    give_priority(game, next, 0);
+}
+
+/// The player does not respond to the top of the stack.
+/// Once every player passes in a row the top effect resolves.
+pub fn pass_priority(game: &mut Game, player_id: PlayerId) -> Result {
+    let priority = game.priority.ok_or(Error::WrongState)?;
+    if priority.holder != player_id {
+        return Err(Error::NotFromRightPlayer);
+    }
+    let passes = priority.passes + 1;
+    let alive = game.players.iter().filter(|p| p.is_alive()).count();
+    if passes < alive {
+        let next = next_player(game, player_id);
+        give_priority(game, next, passes);
+        return Ok(OkCode::Done);
+    }
+
+    let left = game.stack.len().saturating_sub(1);
+    resolve_top(game);
+    if game.stack.is_empty() {
+        game.priority = None;
+    } else if game.stack.len() == left {
+        // Nothing new was pushed so the active player may respond to the next effect.
+        let active = game.active_player_id();
+        give_priority(game, active, 0);
+    }
+    Ok(OkCode::Done)
+}
+
+/// The player holding priority if they have to pass now,
+/// because they ran out of time or have no instant they can play.
+pub fn forced_pass(game: &Game) -> Option<PlayerId> {
+    let priority = game.priority?;
+    if priority.time_left() == Duration::from_secs(0) || !can_respond(game, priority.holder) {
+        Some(priority.holder)
+    } else {
+        None
+    }
+}
+
+/// True if the player has an instant in hand they can pay for.
+fn can_respond(game: &Game, player_id: PlayerId) -> bool {
+    let player = &game.players[player_id];
+    player
+        .zones
+        .cards_in(ZoneName::Hand)
+        .iter()
+        .filter_map(|key| game.cards.get(*key))
+        .any(|card| card.has(TagKey::Instant) && player.resources.can_pay(card.current_cost()))
+}
+
+/// Resolves the top of the stack. Returns false if the stack was empty.
+pub fn resolve_top(game: &mut Game) -> bool {
+    let effect = match game.stack.pop_back() {
+        Some(effect) => effect,
+        None => return false,
+    };
+    info!("Resolving {}.", effect.name());
+    for command in effects::resolve(game, effect.source(), effect.owner(), effect.node()) {
+        game.apply_command(command);
+    }
+    let action = Action::EffectResolved {
+        name: String::from(effect.name()),
+    };
+    game.send_all_action(&action).unwrap_or(());
+    game.run_cleanup();
+    true
+}
+
+/// Resolves everything on the stack without waiting for responses.
+/// Used when the game moves on while effects are still waiting.
+pub fn resolve_all(game: &mut Game) {
+    for _ in 0..MAX_RESOLVE {
+        if !resolve_top(game) {
+            game.priority = None;
+            return;
+        }
+    }
+    warn!("Effects kept pushing more effects. Dropped {}.", game.stack.len());
+    game.stack.clear();
+    game.priority = None;
+}
+
+/// The next player after player_id who is still in the game.
+fn next_player(game: &Game, player_id: PlayerId) -> PlayerId {
+    let count = game.players.len();
+    (1..=count)
+        .map(|i| (player_id + i) % count)
+        .find(|p| game.players[*p].is_alive())
+        .unwrap_or(player_id)
+}
+
+fn give_priority(game: &mut Game, holder: PlayerId, passes: usize) {
+    game.priority = Some(Priority {
+        holder,
+        passes,
+        expires: Instant::now() + PRIORITY_TIME,
+    });
+    // Players who can not respond are passed for by the stage without being asked,
+    // so their client does not wait for input it will never need.
+    if can_respond(game, holder) {
+        game.send_all_action(&Action::PriorityTo(holder)).unwrap_or(());
+    }
+}
@@ -1,5 +1,10 @@
+use crate::entity::{CardKey, TagKey, Trigger};
 use crate::game::action::{Action, Actor, OkCode};
-use crate::game::{Game, GameSettings, NetPlayerId, Phase, Turn};
+use crate::game::effects;
+use crate::game::stack;
+use crate::game::target::TargetQuery;
+use crate::game::zones::ZoneName;
+use crate::game::{ActionError, Game, GameSettings, NetPlayerId, Phase, PlayerId, Turn};
 use crate::net::{Connection, NetError};
 use crate::utils::timer::Timer;
 use std::collections::VecDeque;
@@ -11,6 +16,8 @@ use std::time::{Duration, Instant};
 // Message from clients to game loop.
 pub enum NetRelay {
     Open(NetPlayerId, Connection),
+    /// A spectator connected.
+    Watch(NetPlayerId, Connection),
     Close(NetPlayerId),
     Act(NetPlayerId, Action),
     Start(),
@@ -58,6 +65,7 @@ impl Stage {
         for conn in game.connections() {
             conn.disconnect();
         }
+        game.spectators.close();
     }
     /// Reads all from recv. Returns `Ok(())` if everything was read.
     /// Returns `Err(RecvError)` if the channel was disconnected
@@ -80,12 +88,27 @@ impl Stage {
     /// Returns `Err(RecvError)` if the channel was disconnected
     fn get_recv(&mut self, mut game: &mut Game) -> Result<(), NetError> {
         loop {
+            if game.has_authority() {
+                if let Some(holder) = stack::forced_pass(game) {
+                    info!("Player #{} passes priority.", holder);
+                    self.action_queue
+                        .push_back((Actor::User(holder), Action::PassPriority));
+                    break;
+                }
+            }
             let relay = if self.state.should_wait_for_timeout() {
-                self.recv.recv_timeout(game.timer.time_left())
+                // Wake up when the player holding priority runs out of time.
+                let wait = match game.priority {
+                    Some(priority) => game.timer.time_left().min(priority.time_left()),
+                    None => game.timer.time_left(),
+                };
+                self.recv.recv_timeout(wait)
             } else {
-                self.recv
-                    .try_recv()
-                    .map_err(|_e| RecvTimeoutError::Disconnected)
+                // States with no duration time out as soon as nothing is left to read.
+                self.recv.try_recv().map_err(|e| match e {
+                    TryRecvError::Empty => RecvTimeoutError::Timeout,
+                    TryRecvError::Disconnected => RecvTimeoutError::Disconnected,
+                })
             };
 
             match relay {
@@ -94,9 +117,22 @@ impl Stage {
                     conn.destroy();
                     *conn = connection;
                 }
-                Ok(NetRelay::Close(index)) => {
+                Ok(NetRelay::Watch(_index, connection)) => {
+                    let turn = match self.state {
+                        State::PlayerTurn(turn) => Some(turn),
+                        _ => None,
+                    };
+                    game.add_spectator(connection, turn);
+                }
+                Ok(NetRelay::Close(index)) if game.spectators.contains(index) => {
+                    game.spectators.leave(index);
+                }
+                Ok(NetRelay::Close(index)) if index < game.connections().len() => {
                     game.connection(index).destroy();
                 }
+                Ok(NetRelay::Close(index)) => {
+                    debug!("Ignored close for connection #{} which is not in the game.", index);
+                }
                 Ok(NetRelay::Start()) => {
                     debug_assert!(game.network_mode().is_server());
                     debug_assert_eq!(State::Waiting, self.state);
@@ -110,11 +146,18 @@ impl Stage {
                 }
                 Ok(NetRelay::Act(player_id, mut action)) => {
                     let mut actor = Actor::User(player_id);
-                    if self.validate_action(&mut actor, &mut action).is_ok() {
-                        self.action_queue.push_back((actor, action));
-                        break;
+                    match self.validate_action(game, &mut actor, &mut action) {
+                        Ok(()) => {
+                            self.action_queue.push_back((actor, action));
+                            break;
+                        }
+                        Err(e) => {
+                            warn!("Rejected {:?} from {}: {}", action, actor, e);
+                            game.send_action(player_id, &Action::OnResponceErr(e))?;
+                        }
                     }
                 }
+                Err(RecvTimeoutError::Timeout) if stack::forced_pass(game).is_some() => (),
                 Err(RecvTimeoutError::Timeout) => {
                     info!("Timeout");
                     let next = self.state.timeout(game);
@@ -137,15 +180,137 @@ impl Stage {
         }
         Ok(())
     }
-    fn validate_action(&mut self, actor: &mut Actor, action: &mut Action) -> Result<(), ()> {
-        Ok(())
+    /// Checks that an action sent by a player is allowed before it is queued.
+    /// Clients trust everything sent from the server.
+    fn validate_action(
+        &mut self,
+        game: &Game,
+        actor: &mut Actor,
+        action: &mut Action,
+    ) -> Result<(), ActionError> {
+        if !game.has_authority() {
+            return Ok(());
+        }
+        let player_id = match actor {
+            Actor::User(id) if *id < game.players.len() => *id,
+            _ => return Err(ActionError::NotFromRightPlayer),
+        };
+
+        match action {
+            Action::Text(_) | Action::OnResponceOk(_) | Action::OnResponceErr(_) => Ok(()),
+            // Only a game that has started can be conceded.
+            Action::Concede => match self.state {
+                State::Mulligan | State::PlayerTurn(_) => Ok(()),
+                _ => Err(ActionError::WrongState),
+            },
+            _ if game.priority.is_some() => Stage::validate_response(game, player_id, action),
+            Action::PassPriority => Err(ActionError::WrongState),
+            Action::SetDeck(_) | Action::ReadyToPlay() => match self.state {
+                State::GameSetup => Ok(()),
+                _ => Err(ActionError::WrongState),
+            },
+            Action::MuliginResult { .. } => match self.state {
+                State::Mulligan => Ok(()),
+                _ => Err(ActionError::WrongState),
+            },
+            Action::EndTurn(p) => {
+                if *p != player_id {
+                    return Err(ActionError::NotFromRightPlayer);
+                }
+                self.validate_turn(player_id)
+            }
+            Action::UseHeroPower | Action::HeroAttack(_) => {
+                self.validate_turn(player_id)
+            }
+            Action::QueryTargets(query) => Stage::validate_query(game, player_id, query),
+            Action::PlayCard(key, _) => {
+                self.validate_turn(player_id)?;
+                Stage::validate_card(game, player_id, *key, ZoneName::Hand)
+            }
+            Action::DeclareAttack(attacker, _) => {
+                self.validate_turn(player_id)?;
+                Stage::validate_card(game, player_id, *attacker, ZoneName::Battlefield)
+            }
+            // Everything else is only sent from the server.
+            _ => Err(ActionError::NotSupported),
+        }
+    }
+    /// Checks an action sent while effects on the stack wait for responses.
+    /// Only the player with priority may act, by passing or playing an instant.
+    fn validate_response(
+        game: &Game,
+        player_id: PlayerId,
+        action: &Action,
+    ) -> Result<(), ActionError> {
+        match game.priority {
+            Some(priority) if priority.holder != player_id => {
+                return Err(ActionError::NotFromRightPlayer)
+            }
+            _ => (),
+        }
+        match action {
+            Action::PassPriority => Ok(()),
+            Action::QueryTargets(query) => Stage::validate_query(game, player_id, query),
+            Action::PlayCard(key, _) => {
+                Stage::validate_card(game, player_id, *key, ZoneName::Hand)?;
+                match game.cards.get(*key) {
+                    Some(card) if card.get_tag(&TagKey::Instant).as_bool() => Ok(()),
+                    _ => Err(ActionError::WrongState),
+                }
+            }
+            _ => Err(ActionError::WrongState),
+        }
+    }
+    /// Checks that it is the player's turn and they are in the Play phase.
+    fn validate_turn(&self, player_id: PlayerId) -> Result<(), ActionError> {
+        match self.state {
+            State::PlayerTurn(turn) if turn.player() != player_id => {
+                Err(ActionError::NotFromRightPlayer)
+            }
+            State::PlayerTurn(turn) if turn.phase() == Phase::Play => Ok(()),
+            _ => Err(ActionError::WrongState),
+        }
+    }
+    /// Checks that a target query does not ask about a card the player may not see.
+    fn validate_query(
+        game: &Game,
+        player_id: PlayerId,
+        query: &TargetQuery,
+    ) -> Result<(), ActionError> {
+        match query {
+            TargetQuery::PlayCard(key) => {
+                Stage::validate_card(game, player_id, *key, ZoneName::Hand)
+            }
+            TargetQuery::Attack(_) | TargetQuery::HeroAttack => Ok(()),
+        }
+    }
+    /// Checks that the player controls the card and it is in the zone.
+    fn validate_card(
+        game: &Game,
+        player_id: PlayerId,
+        key: CardKey,
+        zone: ZoneName,
+    ) -> Result<(), ActionError> {
+        match game.cards.placement(key) {
+            Some(p) if p.controller != player_id => Err(ActionError::NotFromRightPlayer),
+            Some(p) if p.zone == Some(zone) => Ok(()),
+            _ => Err(ActionError::InvalidParamaters),
+        }
     }
     /// Returns true when a state changse is needed.
     /// TODO all actions in queue are performed with this connection
     /// TODO watch for infinit loops.
     fn process_actions(&mut self, game: &mut Game) -> Result<(), NetError> {
         while let Some(action) = self.action_queue.pop_front() {
-            match action.1.perform(game, &action.0) {
+            let result = action.1.perform(game, &action.0);
+            if game.has_authority() {
+                game.run_cleanup();
+                if let Some(res) = game.check_game_over() {
+                    self.state.transition_to(game, State::Done(res));
+                    return Ok(());
+                }
+            }
+            match result {
                 Ok(OkCode::ChangeState) => {
                     let next = self.state.next(game);
                     self.state.transition_to(game, next);
@@ -153,12 +318,12 @@ impl Stage {
                 Ok(OkCode::Done) => (),
                 Ok(code) => {
                     let a = Action::OnResponceOk(code);
-                    game.connection(action.0.id()).send(&a)?;
+                    game.send_action(action.0.id(), &a)?;
                 }
                 Err(e) => {
                     info!("action err: {:?}", e);
                     let a = Action::OnResponceErr(e);
-                    game.connection(action.0.id()).send(&a)?;
+                    game.send_action(action.0.id(), &a)?;
                 }
             }
         }
@@ -173,6 +338,7 @@ pub enum State {
     Waiting,
     GameSetup,
     GameStart,
+    Mulligan,
     PlayerTurn(Turn),
     Done(GameResults),
 }
@@ -180,6 +346,8 @@ pub enum State {
 #[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
 pub enum GameResults {
     PlayerWin(usize),
+    /// All remaining players lost at the same time.
+    Draw,
     OutOfTurns,
     StopAndExit,
     NotAllPlayersReady,
@@ -203,6 +371,7 @@ impl State {
             State::Waiting => Duration::from_secs(15),
             State::GameSetup => Duration::from_millis(50),
             State::GameStart => Duration::from_millis(50),
+            State::Mulligan => Duration::from_secs(20),
             State::PlayerTurn(turn) => turn.get_duration(),
             State::Done(_) => Duration::new(0, 0),
         }
@@ -210,15 +379,32 @@ impl State {
 
     pub fn transition_to(&mut self, game: &mut Game, mut next: State) {
         self.exit(game);
+        // Effects resolved while leaving a state can end the game.
+        if let Some(res) = State::game_over(game, &next) {
+            next = State::Done(res);
+        }
         *self = next;
         next.enter(game);
+        // So can triggers from entering one, like the start or end of a turn.
+        if let Some(res) = State::game_over(game, self) {
+            self.transition_to(game, State::Done(res));
+        }
+    }
+
+    /// The result of the game if it ended and the state is not already Done.
+    fn game_over(game: &Game, state: &State) -> Option<GameResults> {
+        if state.is_done() || !game.has_authority() {
+            return None;
+        }
+        game.check_game_over()
     }
 
     fn next(&mut self, _game: &mut Game) -> State {
         match self {
             State::Waiting => State::Waiting,
             State::GameSetup => State::GameStart,
-            State::GameStart => State::PlayerTurn(Turn::new(0, 1, Phase::Start)),
+            State::GameStart => State::Mulligan,
+            State::Mulligan => State::PlayerTurn(Turn::new(0, 1, Phase::Start)),
             State::PlayerTurn(turn) => State::next_player_turn(turn),
             State::Done(_) => panic!("State::Done can not have a next() state."),
         }
@@ -228,7 +414,8 @@ impl State {
         match self {
             State::Waiting => State::Done(GameResults::NotAllPlayersConncted),
             State::GameSetup => State::Done(GameResults::NotAllPlayersReady),
-            State::GameStart => State::PlayerTurn(Turn::new(0, 1, Phase::Start)),
+            State::GameStart => State::Mulligan,
+            State::Mulligan => State::PlayerTurn(Turn::new(0, 1, Phase::Start)),
             State::PlayerTurn(turn) => State::next_player_turn(turn),
             State::Done(_) => panic!("State::Done can not have a next() state."),
         }
@@ -241,11 +428,17 @@ impl State {
             State::GameStart => {
                 if game.network_mode().is_server() {
                     game.send_all_action(&Action::GameStart());
+                    game.dispatch.broadcast(Trigger::OnGameStart());
                     game.shuffle_decks();
-                    game.run_mulligan();
+                    game.deal_starting_hands();
                     // game.queue_action(0, Action::StartNextTurn());
                 }
             }
+            State::Mulligan => {
+                if game.network_mode().is_server() {
+                    game.start_mulligan();
+                }
+            }
             State::GameSetup => {
                 if game.network_mode().is_server() {
                     game.send_all_action(&Action::BeginGameSetup());
@@ -253,18 +446,48 @@ impl State {
             }
             State::PlayerTurn(turn) => {
                 if game.network_mode().is_server() {
-                    let act = &Action::SwitchTurn(*turn);
-                    for player in game.connections() {
-                        player.send(act).unwrap();
+                    match turn.phase() {
+                        Phase::Start => {
+                            game.set_active_player(turn.player());
+                            game.start_turn(turn.player());
+                            game.dispatch.broadcast(Trigger::OnTurnStart(turn.player()));
+                        }
+                        Phase::Draw => game.draw_cards(turn.player(), 1),
+                        Phase::End => {
+                            game.dispatch.broadcast(Trigger::OnTurnEnd(turn.player()));
+                        }
+                        _ => (),
                     }
+                    game.run_cleanup();
+                    game.send_all_action(&Action::SwitchTurn(*turn)).unwrap();
                 }
             }
             State::Waiting => (),
-            State::Done(_) => (),
+            State::Done(res) => {
+                if game.network_mode().is_server() {
+                    game.dispatch.broadcast(Trigger::OnGameEnd(*res));
+                    game.send_all_action(&Action::GameEnd(*res)).unwrap_or(());
+                }
+            }
         }
     }
 
-    fn exit(&mut self, _game: &mut Game) {}
+    fn exit(&mut self, game: &mut Game) {
+        if !game.network_mode().is_server() {
+            return;
+        }
+        // The game does not wait for responses once the state is over.
+        stack::resolve_all(game);
+        match self {
+            State::Mulligan => game.end_mulligan(),
+            State::PlayerTurn(turn) if turn.phase() == Phase::End => {
+                game.dispatch.broadcast(Trigger::OnBetweenTurns());
+                effects::end_turn(game);
+                game.run_cleanup();
+            }
+            _ => (),
+        }
+    }
 
     fn next_player_turn(last_turn: &Turn) -> State {
         if let Some(turn) = last_turn.next() {
@@ -0,0 +1,132 @@
+use crate::entity::ability::{Side, When};
+use crate::entity::{CardKey, CardPool, EffectNode, TagKey};
+use crate::game::combat;
+use crate::game::zones::ZoneName;
+use crate::game::{Game, PlayerId};
+
+/// What an action or effect is aimed at.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Target {
+    None,
+    Card(CardKey),
+    Hero(PlayerId),
+    Zone(PlayerId, ZoneName),
+}
+
+/// The things a TargetFilter lets through.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TargetKind {
+    /// Cards on a battlefield.
+    Minion,
+    Hero,
+    /// Minions and heroes.
+    Character,
+}
+
+/// Which targets are allowed, seen from a player. Such as "enemy minion" or "friendly character".
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct TargetFilter {
+    pub side: Side,
+    pub kind: TargetKind,
+}
+
+impl TargetFilter {
+    pub fn new(side: Side, kind: TargetKind) -> TargetFilter {
+        TargetFilter { side, kind }
+    }
+
+    fn on_side(&self, player_id: PlayerId, owner: PlayerId) -> bool {
+        match self.side {
+            Side::Friendly => owner == player_id,
+            Side::Enemy => owner != player_id,
+            Side::Any => true,
+        }
+    }
+
+    /// Every target the player may pick with this filter.
+    /// Enemy cards with Stealth are left out.
+    pub fn targets(&self, game: &Game, player_id: PlayerId) -> Vec<Target> {
+        let mut targets = Vec::new();
+        for player in game.players.iter() {
+            if !self.on_side(player_id, player.player_id) {
+                continue;
+            }
+            if self.kind != TargetKind::Hero {
+                for key in player.zones.cards_in(ZoneName::Battlefield).iter() {
+                    let hidden = player.player_id != player_id
+                        && game.cards.get(*key).is_some_and(|c| c.has(TagKey::Stealth));
+                    if !hidden {
+                        targets.push(Target::Card(*key));
+                    }
+                }
+            }
+            if self.kind != TargetKind::Minion {
+                targets.push(Target::Hero(player.player_id));
+            }
+        }
+        targets
+    }
+
+    /// True if the player may pick the target with this filter.
+    pub fn allows(&self, game: &Game, player_id: PlayerId, target: Target) -> bool {
+        self.targets(game, player_id).contains(&target)
+    }
+}
+
+/// What a client asks the legal targets of.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum TargetQuery {
+    /// What the card on the player's battlefield can attack.
+    Attack(CardKey),
+    /// What the player's hero can attack.
+    HeroAttack,
+    /// What can be picked for the card's effects when it is played from hand.
+    PlayCard(CardKey),
+}
+
+/// Lists every target that would be accepted for the query.
+/// Empty when the action can not be done at all.
+pub fn legal_targets(game: &Game, player_id: PlayerId, query: TargetQuery) -> Vec<Target> {
+    let enemies = TargetFilter::new(Side::Enemy, TargetKind::Character);
+    match query {
+        TargetQuery::Attack(attacker) => enemies
+            .targets(game, player_id)
+            .into_iter()
+            .filter(|t| combat::validate_attack(game, player_id, attacker, *t).is_ok())
+            .collect(),
+        TargetQuery::HeroAttack => {
+            if !game.players[player_id].hero.can_attack() {
+                return Vec::new();
+            }
+            enemies
+                .targets(game, player_id)
+                .into_iter()
+                .filter(|t| combat::validate_defender(game, player_id, *t).is_ok())
+                .collect()
+        }
+        // Only the player holding the card may learn what it can target.
+        TargetQuery::PlayCard(key) if !in_hand(game, player_id, key) => Vec::new(),
+        TargetQuery::PlayCard(key) => match play_filter(game, key) {
+            Some(filter) => filter.targets(game, player_id),
+            None => vec![Target::None],
+        },
+    }
+}
+
+/// True if the card is in the player's hand.
+fn in_hand(game: &Game, player_id: PlayerId, key: CardKey) -> bool {
+    game.cards
+        .placement(key)
+        .is_some_and(|p| p.controller == player_id && p.zone == Some(ZoneName::Hand))
+}
+
+/// The filter for the target the player picks when playing the card, if it needs one.
+pub fn play_filter(game: &Game, key: CardKey) -> Option<TargetFilter> {
+    let card = game.cards.get(key)?;
+    let data = CardPool::lookup_name(card.name())?;
+    data.abilities()
+        .iter()
+        .filter(|a| a.when == When::Play)
+        .find_map(|a| a.effect.chosen_side())
+        .map(|side| TargetFilter::new(side, TargetKind::Minion))
+}
@@ -0,0 +1,127 @@
+//! What each player may see. The server rewrites every action it sends for the player
+//! on the other end, so cards in hidden zones, like decks and other players' hands,
+//! are never named over the wire.
+use crate::game::zones::ZoneName;
+use crate::game::{Action, PlayerId};
+use crate::net::{Connection, NetResult};
+
+/// True if the viewer may see which cards are in one of the player's zones.
+pub fn can_see(viewer: PlayerId, player_id: PlayerId, zone: ZoneName) -> bool {
+    match zone {
+        ZoneName::Battlefield | ZoneName::Graveyard | ZoneName::Banished => true,
+        ZoneName::Hand => viewer == player_id,
+        ZoneName::Deck | ZoneName::Limbo => false,
+    }
+}
+
+/// Rewrites the action for the viewer. Returns None when the viewer may see all of it.
+pub fn redact(viewer: PlayerId, action: &Action) -> Option<Action> {
+    match action {
+        Action::DrawCardKnown(player_id, _) if *player_id != viewer => {
+            Some(Action::DrawCardAnon(*player_id, 1))
+        }
+        Action::CardMoved {
+            card: Some(_),
+            player_id,
+            from,
+            to,
+        } => {
+            // A card moved between hidden zones, like from a hand back into its deck.
+            let seen_from = from.is_some_and(|(from_player, from)| can_see(viewer, from_player, from));
+            if seen_from || can_see(viewer, *player_id, *to) {
+                None
+            } else {
+                Some(Action::CardMoved {
+                    card: None,
+                    player_id: *player_id,
+                    from: *from,
+                    to: *to,
+                })
+            }
+        }
+        Action::ZoneContents {
+            player_id,
+            zone,
+            cards,
+        } if !can_see(viewer, *player_id, *zone) => Some(Action::ZoneContents {
+            player_id: *player_id,
+            zone: *zone,
+            cards: vec![None; cards.len()],
+        }),
+        _ => None,
+    }
+}
+
+/// Sends the action over the connection, rewritten for the player on the other end.
+pub fn send(conn: &Connection, action: &Action) -> NetResult<()> {
+    match redact(conn.player_id(), action) {
+        Some(redacted) => conn.send(&redacted),
+        None => conn.send(action),
+    }
+}
+
+/// Sends the action to every connection, rewritten for each player.
+pub fn send_all(connections: &[Connection], action: &Action) -> NetResult<()> {
+    for conn in connections {
+        send(conn, action)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn moved(card: Option<u64>, from: Option<(PlayerId, ZoneName)>, to: ZoneName) -> Action {
+        Action::CardMoved {
+            card,
+            player_id: 0,
+            from,
+            to,
+        }
+    }
+
+    #[test]
+    fn hides_other_players_draws() {
+        let drawn = Action::DrawCardKnown(0, 7);
+        assert!(redact(0, &drawn).is_none());
+        match redact(1, &drawn) {
+            Some(Action::DrawCardAnon(0, 1)) => (),
+            other => panic!("expected an anonymous draw, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn hides_cards_moved_between_hidden_zones() {
+        let to_deck = moved(Some(7), Some((0, ZoneName::Hand)), ZoneName::Deck);
+        assert!(redact(0, &to_deck).is_none());
+        match redact(1, &to_deck) {
+            Some(Action::CardMoved { card: None, .. }) => (),
+            other => panic!("expected a hidden card, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn shows_cards_moved_to_or_from_public_zones() {
+        let played = moved(Some(7), Some((0, ZoneName::Hand)), ZoneName::Battlefield);
+        assert!(redact(1, &played).is_none());
+        let bounced = moved(Some(7), Some((1, ZoneName::Battlefield)), ZoneName::Hand);
+        assert!(redact(1, &bounced).is_none());
+        let created = moved(Some(7), None, ZoneName::Deck);
+        assert!(redact(1, &created).is_some());
+    }
+
+    #[test]
+    fn hides_contents_of_hidden_zones() {
+        let hand = Action::ZoneContents {
+            player_id: 0,
+            zone: ZoneName::Hand,
+            cards: vec![Some(1), Some(2)],
+        };
+        assert!(redact(0, &hand).is_none());
+        match redact(1, &hand) {
+            Some(Action::ZoneContents { cards, .. }) => assert_eq!(cards, vec![None, None]),
+            other => panic!("expected hidden contents, got {:?}", other),
+        }
+    }
+}
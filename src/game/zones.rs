@@ -1,4 +1,6 @@
-use crate::game::PlayerId;
+use crate::game::{GameRng, PlayerId};
+use rand::seq::SliceRandom;
+use rand::Rng;
 use crate::entity::CardKey;
 
 const DEF_BANISHED_SIZE: usize = 0;
@@ -15,7 +17,7 @@ const MAX_LIMBO_SIZE: usize = 1000;
 const MAX_GRAVEYARD_SIZE: usize = 1000;
 const MAX_HAND_SIZE: usize = 10;
 
-#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Serialize, Deserialize)]
 pub enum ZoneName {
     Banished,
     Battlefield,
@@ -24,13 +26,35 @@ pub enum ZoneName {
     Graveyard,
     Hand,
 }
+impl ZoneName {
+    pub const ALL: [ZoneName; 6] = [
+        ZoneName::Banished,
+        ZoneName::Battlefield,
+        ZoneName::Deck,
+        ZoneName::Limbo,
+        ZoneName::Graveyard,
+        ZoneName::Hand,
+    ];
+    /// The most cards this zone can hold.
+    pub fn max_size(self) -> usize {
+        match self {
+            ZoneName::Banished => MAX_BANISHED_SIZE,
+            ZoneName::Battlefield => MAX_BATTLEFIELD_SIZE,
+            ZoneName::Deck => MAX_DECK_SIZE,
+            ZoneName::Limbo => MAX_LIMBO_SIZE,
+            ZoneName::Graveyard => MAX_GRAVEYARD_SIZE,
+            ZoneName::Hand => MAX_HAND_SIZE,
+        }
+    }
+}
+
 pub trait Zone<T> {
     // Inserts value at this location
-    fn insert_at(&mut self, location: Location, element: T) -> &mut T;
-    fn insert_all_at(&mut self, location: Location, element: Vec<T>);
+    fn insert_at(&mut self, location: Location, element: T, rng: &mut GameRng) -> &mut T;
+    fn insert_all_at(&mut self, location: Location, element: Vec<T>, rng: &mut GameRng);
     // Removes value at this location and return it.
-    fn remove_at(&mut self, location: Location) -> Option<T>;
-    fn remove_x_at(&mut self, count: usize, location: Location) -> Vec<Option<T>>;
+    fn remove_at(&mut self, location: Location, rng: &mut GameRng) -> Option<T>;
+    fn remove_x_at(&mut self, count: usize, location: Location, rng: &mut GameRng) -> Vec<Option<T>>;
 }
 
 #[derive(Clone, Copy, Debug, Serialize, Deserialize)]
@@ -49,20 +73,21 @@ pub enum Location {
     Index(usize),
 }
 
+/// A player's zones. Cards are only added through insert_at so no zone grows past its max size.
 #[derive(Clone, Debug)]
 pub struct ZoneCollection {
     pub player: PlayerId,
-    pub banished: Vec<CardKey>,
-    pub battlefield: Vec<CardKey>,
-    pub deck: Vec<CardKey>,
-    pub limbo: Vec<CardKey>,
-    pub graveyard: Vec<CardKey>,
-    pub hand: Vec<CardKey>,
+    banished: Vec<CardKey>,
+    battlefield: Vec<CardKey>,
+    deck: Vec<CardKey>,
+    limbo: Vec<CardKey>,
+    graveyard: Vec<CardKey>,
+    hand: Vec<CardKey>,
 }
 
 impl Zone<CardKey> for Vec<CardKey> {
     // Inserts value at this location
-    fn insert_at(&mut self, location: Location, element: CardKey) -> &mut CardKey {
+    fn insert_at(&mut self, location: Location, element: CardKey, rng: &mut GameRng) -> &mut CardKey {
         match location {
             Location::Default => {
                 self.push(element);
@@ -77,12 +102,15 @@ impl Zone<CardKey> for Vec<CardKey> {
                 self.first_mut().unwrap()
             }
             Location::Random => {
-                self.push(element);
-                self.last_mut().unwrap()
+                let i = rng.gen_range(0..=self.len());
+                self.insert(i, element);
+                self.get_mut(i).unwrap()
             }
             Location::Shuffle => {
                 self.push(element);
-                self.last_mut().unwrap()
+                self.shuffle(rng);
+                let i = self.iter().position(|k| *k == element).unwrap();
+                self.get_mut(i).unwrap()
             }
             Location::Index(index) => {
                 let i = Ord::min(index, self.len());
@@ -92,19 +120,29 @@ impl Zone<CardKey> for Vec<CardKey> {
         }
     }
     // Removes value at this location and return it.
-    fn remove_at(&mut self, location: Location) -> Option<CardKey> {
+    fn remove_at(&mut self, location: Location, rng: &mut GameRng) -> Option<CardKey> {
         match location {
             Location::Default => self.pop(),
             Location::Top => self.pop(),
             Location::Bottom => {
-                if self.len() == 0 {
+                if self.is_empty() {
                     None
                 } else {
                     Some(self.remove(0))
                 }
             }
-            Location::Shuffle => self.pop(),
-            Location::Random => self.pop(),
+            Location::Shuffle => {
+                self.shuffle(rng);
+                self.pop()
+            }
+            Location::Random => {
+                if self.is_empty() {
+                    None
+                } else {
+                    let i = rng.gen_range(0..self.len());
+                    Some(self.remove(i))
+                }
+            }
             Location::Index(index) => {
                 if index >= self.len() {
                     None
@@ -115,16 +153,16 @@ impl Zone<CardKey> for Vec<CardKey> {
         }
     }
 
-    fn insert_all_at(&mut self, location: Location, cards: Vec<CardKey>) {
+    fn insert_all_at(&mut self, location: Location, cards: Vec<CardKey>, rng: &mut GameRng) {
         for card in cards {
-            self.insert_at(location, card);
+            self.insert_at(location, card, rng);
         }
     }
 
-    fn remove_x_at(&mut self, count: usize, location: Location) -> Vec<Option<CardKey>> {
+    fn remove_x_at(&mut self, count: usize, location: Location, rng: &mut GameRng) -> Vec<Option<CardKey>> {
         let mut vec = Vec::with_capacity(count);
         for _ in 0..count {
-            vec.push(self.remove_at(location));
+            vec.push(self.remove_at(location, rng));
         }
         vec
     }
@@ -142,7 +180,17 @@ impl ZoneCollection {
             hand: Vec::with_capacity(DEF_HAND_SIZE),
         }
     }
-    pub fn get_mut(&mut self, zone: ZoneName) -> &mut Zone<CardKey> {
+    fn vec(&self, zone: ZoneName) -> &Vec<CardKey> {
+        match zone {
+            ZoneName::Banished => &self.banished,
+            ZoneName::Battlefield => &self.battlefield,
+            ZoneName::Deck => &self.deck,
+            ZoneName::Limbo => &self.limbo,
+            ZoneName::Graveyard => &self.graveyard,
+            ZoneName::Hand => &self.hand,
+        }
+    }
+    fn vec_mut(&mut self, zone: ZoneName) -> &mut Vec<CardKey> {
         match zone {
             ZoneName::Banished => &mut self.banished,
             ZoneName::Battlefield => &mut self.battlefield,
@@ -152,6 +200,84 @@ impl ZoneCollection {
             ZoneName::Hand => &mut self.hand,
         }
     }
+    /// Finds the zone that holds the card.
+    pub fn zone_of(&self, key: CardKey) -> Option<ZoneName> {
+        ZoneName::ALL.iter().cloned().find(|zone| self.vec(*zone).contains(&key))
+    }
+    /// The cards in the zone from bottom to top.
+    pub fn cards_in(&self, zone: ZoneName) -> &[CardKey] {
+        self.vec(zone)
+    }
+    pub fn contains(&self, zone: ZoneName, key: CardKey) -> bool {
+        self.vec(zone).contains(&key)
+    }
+    pub fn len(&self, zone: ZoneName) -> usize {
+        self.vec(zone).len()
+    }
+    /// True when the zone has no room for another card.
+    pub fn is_full(&self, zone: ZoneName) -> bool {
+        self.vec(zone).len() >= zone.max_size()
+    }
+    /// Inserts the card into the zone if there is room for it.
+    /// Gives the card back as Err when the zone is full.
+    pub fn insert_at(
+        &mut self,
+        zone: ZoneName,
+        location: Location,
+        key: CardKey,
+        rng: &mut GameRng,
+    ) -> Result<(), CardKey> {
+        if self.is_full(zone) {
+            return Err(key);
+        }
+        self.vec_mut(zone).insert_at(location, key, rng);
+        Ok(())
+    }
+    /// Inserts the cards into the zone one at a time while there is room.
+    /// Gives back the cards that did not fit as Err.
+    pub fn insert_all_at(
+        &mut self,
+        zone: ZoneName,
+        location: Location,
+        keys: Vec<CardKey>,
+        rng: &mut GameRng,
+    ) -> Result<(), Vec<CardKey>> {
+        let mut left = Vec::new();
+        for key in keys {
+            if let Err(key) = self.insert_at(zone, location, key, rng) {
+                left.push(key);
+            }
+        }
+        if left.is_empty() {
+            Ok(())
+        } else {
+            Err(left)
+        }
+    }
+    /// Takes the card out of the zone. Returns false if it was not there.
+    pub fn remove(&mut self, zone: ZoneName, key: CardKey) -> bool {
+        let cards = self.vec_mut(zone);
+        let len = cards.len();
+        cards.retain(|k| *k != key);
+        cards.len() != len
+    }
+    /// Takes count cards from the location of the zone. None for each card the zone ran out of.
+    pub fn remove_x_at(
+        &mut self,
+        zone: ZoneName,
+        count: usize,
+        location: Location,
+        rng: &mut GameRng,
+    ) -> Vec<Option<CardKey>> {
+        self.vec_mut(zone).remove_x_at(count, location, rng)
+    }
+    /// Takes every card out of the zone.
+    pub fn clear(&mut self, zone: ZoneName) -> Vec<CardKey> {
+        self.vec_mut(zone).drain(..).collect()
+    }
+    pub fn shuffle(&mut self, zone: ZoneName, rng: &mut GameRng) {
+        self.vec_mut(zone).shuffle(rng);
+    }
     pub fn get(&self, zone: ZoneName) -> &Zone<CardKey> {
         match zone {
             ZoneName::Banished => &self.banished,
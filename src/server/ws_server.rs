@@ -1,3 +1,4 @@
+use crate::config;
 use crate::game::stage::NetRelay;
 use crate::game::Game;
 use crate::game::{GameSettings, Stage};
@@ -7,12 +8,15 @@ use std::net::ToSocketAddrs;
 use std::sync::mpsc::channel;
 use std::sync::mpsc::Sender as TSender;
 use std::thread;
+use std::time::Duration;
 use ws::Sender as WsSender;
 use ws::{Builder, Factory};
 
 pub fn listen<A: ToSocketAddrs>(ip: A, id: usize, max_players: usize) {
     let settings = ServerConfig::from_disk().into();
-    let game_settings = GameSettings::new(id, max_players, NetworkMode::Server);
+    let game_settings = GameSettings::new(id, max_players, NetworkMode::Server)
+        .with_seed(config::active().game_seed)
+        .with_spectator_delay(Duration::from_millis(config::active().spectator_delay));
     let (send, stage) = Stage::build(game_settings);
     let builder = thread::Builder::new().name(format!("server_{}", id));
     let thread_handle = builder.spawn(move || stage.run_authority());
@@ -21,6 +25,8 @@ pub fn listen<A: ToSocketAddrs>(ip: A, id: usize, max_players: usize) {
         sender: send,
         active_connections: 0,
         max_players,
+        max_spectators: config::active().max_spectators,
+        spectators: 0,
         next_player_id: 0,
     };
     let ws = Builder::new().with_settings(settings).build(factory);
@@ -37,6 +43,9 @@ struct ServerFactory {
     sender: TSender<NetRelay>,
     active_connections: usize,
     max_players: usize,
+    max_spectators: usize,
+    /// Connected spectators.
+    spectators: usize,
     next_player_id: usize,
 }
 impl Factory for ServerFactory {
@@ -47,7 +56,13 @@ impl Factory for ServerFactory {
         self.next_player_id += 1;
 
         let role = if self.next_player_id > self.max_players {
-            Role::GameFull
+            if self.spectators < self.max_spectators {
+                // Connections after the players watch the game.
+                self.spectators += 1;
+                Role::Spectator
+            } else {
+                Role::GameFull
+            }
         } else if self.next_player_id == self.max_players {
             Role::Player(true) // true if final player to connect.
         } else {
@@ -60,6 +75,9 @@ impl Factory for ServerFactory {
     fn connection_lost(&mut self, handle: ServerHandle) {
         info!("Connection #{} lost.", handle.player_id);
         self.active_connections -= 1;
+        if handle.is_spectator() {
+            self.spectators -= 1;
+        }
         // The last connecction will shutdown the server.
         if self.active_connections == 0 {
             info!("All connections lost: Begin shutdown.");
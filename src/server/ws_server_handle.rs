@@ -1,5 +1,5 @@
 use crate::game::stage::NetRelay;
-use crate::game::{Action, NetPlayerId};
+use crate::game::{Action, ActionError, NetPlayerId};
 use crate::net::{Codec, Connection};
 use crate::net::{PROTOCOL, VERSION_HEADER};
 use crate::server::ws_server::Role;
@@ -18,7 +18,6 @@ pub struct ServerHandle {
     pub player_id: NetPlayerId,
     role: Role,
     expire_timeout: Option<Timeout>,
-    mulligin_timeout: Option<Timeout>,
 }
 impl ServerHandle {
     pub fn new(
@@ -33,9 +32,12 @@ impl ServerHandle {
             player_id,
             role,
             expire_timeout: None,
-            mulligin_timeout: None,
         }
     }
+    /// True if this connection only watches the game.
+    pub fn is_spectator(&self) -> bool {
+        matches!(self.role, Role::Spectator)
+    }
 }
 
 fn thread_err<E: StdError>(_e: E) -> Error {
@@ -46,7 +48,6 @@ fn thread_err<E: StdError>(_e: E) -> Error {
 }
 const PING: Token = Token(1);
 const EXPIRE: Token = Token(2);
-const MULIGIN: Token = Token(3);
 
 const GAMESTART: Token = Token(12);
 
@@ -80,10 +81,15 @@ impl Handler for ServerHandle {
                 self.core.send(ev).map_err(thread_err)
             }
             Role::GameFull => self.ws.close(CloseCode::Normal),
-            Role::Spectator => Err(Error::new(
-                ErrorKind::Internal,
-                "Spectator is not implemented",
-            )),
+            Role::Spectator => {
+                let conn = Connection::from_network(self.player_id, self.ws.clone());
+
+                let a = Action::Spectating(self.player_id);
+                conn.send(&a).map_err(thread_err)?;
+
+                let ev = NetRelay::Watch(self.player_id, conn);
+                self.core.send(ev).map_err(thread_err)
+            }
         }
     }
 
@@ -92,6 +98,10 @@ impl Handler for ServerHandle {
             "Connection closing due to ({:?}) {} for player_id {}",
             code, reason, self.player_id
         );
+        // Connections turned away because the game was full never joined it.
+        if let Role::GameFull = self.role {
+            return;
+        }
         if let Err(e) = self.core.send(NetRelay::Close(self.player_id)) {
             warn!("{}", e)
         }
@@ -113,6 +123,10 @@ impl Handler for ServerHandle {
                 self.ws
                     .send(Action::Text(String::from("You know im a computer, right?")))
             }
+            _ if self.is_spectator() => {
+                // Spectators only watch, their actions never reach the game.
+                self.ws.send(Action::OnResponceErr(ActionError::NotFromRightPlayer))
+            }
             _ => {
                 // Any other action is sent to core thread.
                 let ev = NetRelay::Act(self.player_id, action);
@@ -142,10 +156,6 @@ impl Handler for ServerHandle {
                 self.ws.timeout(5_000, PING)
             }
             EXPIRE => self.ws.close(CloseCode::Away),
-            MULIGIN => {
-                let ev = NetRelay::Act(self.player_id, Action::MuliginResult { swap: false });
-                self.core.send(ev).map_err(thread_err)
-            }
             GAMESTART => self.core.send(NetRelay::Start()).map_err(thread_err),
             _ => Err(Error::new(
                 ErrorKind::Internal,
@@ -156,22 +166,12 @@ impl Handler for ServerHandle {
 
     #[inline]
     fn on_new_timeout(&mut self, event: Token, timeout: Timeout) -> Result<()> {
-        match event {
-            EXPIRE => {
-                // Cancel expire if one was scheduled and store the new one.
-                if let Some(t) = self.expire_timeout.take() {
-                    self.ws.cancel(t)?
-                }
-                self.expire_timeout = Some(timeout);
-            }
-            MULIGIN => {
-                // same as expire.
-                if let Some(t) = self.mulligin_timeout.take() {
-                    self.ws.cancel(t)?;
-                }
-                self.mulligin_timeout = Some(timeout);
+        if event == EXPIRE {
+            // Cancel expire if one was scheduled and store the new one.
+            if let Some(t) = self.expire_timeout.take() {
+                self.ws.cancel(t)?
             }
-            _ => (),
+            self.expire_timeout = Some(timeout);
         }
         Ok(())
     }
@@ -12,16 +12,23 @@ impl Input {
         thread::sleep(Duration::from_millis(4));
         let stdin = io::stdin();
         let mut handle = stdin.lock();
-        print!("> ");
-        Input::flush();
-        let mut buffer = String::new();
-        match handle.read_line(&mut buffer) {
-            Ok(_num_bytes) => {
-                if Input::handle_user_input(sender, game, buffer.trim().split(" ").collect()) {
-                    game.queue_action(sender, Action::HandleInput());
+        // Keeps asking until a command ends the player's input, like passing the turn.
+        loop {
+            print!("> ");
+            Input::flush();
+            let mut buffer = String::new();
+            match handle.read_line(&mut buffer) {
+                Ok(0) => return,
+                Ok(_num_bytes) => {
+                    if !Input::handle_user_input(sender, game, buffer.trim().split(" ").collect()) {
+                        return;
+                    }
+                }
+                Err(error) => {
+                    println!("Read input line error: {}", error);
+                    return;
                 }
             }
-            Err(error) => println!("Read input line error: {}", error),
         }
     }
 
@@ -35,7 +42,22 @@ impl Input {
                 }
                 "pass" => {
                     println!("passing the turn");
-                    game.send_action(0, &Action::EndTurn(0));
+                    let end_turn = Action::EndTurn(game.local_player_id);
+                    game.send_action(0, &end_turn).unwrap_or(());
+                    return false;
+                }
+                "power" => {
+                    println!("using hero power");
+                    game.send_action(0, &Action::UseHeroPower).unwrap_or(());
+                }
+                "resolve" => {
+                    println!("letting the stack resolve");
+                    game.send_action(0, &Action::PassPriority).unwrap_or(());
+                    return false;
+                }
+                "concede" => {
+                    println!("conceding the game");
+                    game.send_action(0, &Action::Concede).unwrap_or(());
                     return false;
                 }
                 _ => println!("Unknown command: {:?}", args),
@@ -3,6 +3,8 @@
 // See also: Generational indexes
 // https://kyren.github.io/2018/09/14/rustconf-talk.html
 
+use serde::de::{Deserialize, Deserializer, Error as DeError};
+use serde::ser::{Serialize, Serializer};
 use std::num::NonZeroU32;
 
 type Idx = u32;
@@ -79,7 +81,7 @@ impl IndexKeyAllocator {
     }
 }
 
-#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
 pub struct IndexKey {
     generation: Gen,
     index: Idx,
@@ -111,6 +113,15 @@ impl IndexKey {
     pub fn is_active(&self) -> bool {
         self.generation.get() % 2 == 1
     }
+    /// Packs this key into a single u64 with the generation in the high bits.
+    pub fn to_raw(self) -> u64 {
+        (u64::from(self.generation.get()) << 32) | u64::from(self.index)
+    }
+    /// Unpacks a key made with to_raw. Returns None if the generation is 0.
+    pub fn from_raw(raw: u64) -> Option<IndexKey> {
+        let generation = Gen::new((raw >> 32) as u32)?;
+        Some(IndexKey::with_generation(raw as u32, generation))
+    }
 }
 
 //#[derive(Clone, Eq, PartialEq)]
@@ -157,6 +168,19 @@ impl<T> Default for Entry<T> {
     }
 }
 
+/// Keys are sent as the u64 from to_raw.
+impl Serialize for IndexKey {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_u64(self.to_raw())
+    }
+}
+impl<'de> Deserialize<'de> for IndexKey {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<IndexKey, D::Error> {
+        let raw = u64::deserialize(deserializer)?;
+        IndexKey::from_raw(raw).ok_or_else(|| D::Error::custom("key with a generation of 0"))
+    }
+}
+
 //#[derive(Clone, Eq, PartialEq)]
 pub struct VecMap<T> {
     inner: Vec<Entry<T>>,
@@ -247,6 +271,14 @@ impl<T> VecMap<T> {
         }
     }
 
+    /// Iterates over mutable references to every value that is set.
+    pub fn values_mut(&mut self) -> impl Iterator<Item = &mut T> {
+        self.inner.iter_mut().filter_map(|entry| match entry {
+            Entry::Full(_, value) => Some(value),
+            Entry::Empty => None,
+        })
+    }
+
     /// Set the value for some generational index. May overwrite past generation values.
     /// The overwritten value is returned.
     pub fn insert(&mut self, index: IndexKey, value: T) -> Option<T> {